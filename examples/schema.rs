@@ -4,7 +4,16 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, schema_for};
 
 use cosmwasm_1_to_2_transfer::msg::{
-    ExecuteMsg, GetBalanceResponse, GetOwnerResponse, GetSendFeeResponse, InstantiateMsg, QueryMsg,
+    AccountingResponse, AccountsResponse, ActiveDenomsResponse, ConfigValueResponse,
+    CounterDriftResponse, DefaultPairResponse, ExecuteMsg, FeePoolResponse, FeeSnapshotsResponse,
+    FlagsResponse, ForwardingAddressResponse, GetBalanceResponse, GetConfigResponse,
+    GetOwnerResponse, GetSendFeeResponse, GroupResponse, HoldUntilResponse, HoldersResponse,
+    InstantiateMsg, IsAdminResponse, IsEvenResponse, IsKnownAccountResponse, IsRegisteredResponse,
+    LatestTransferResponse, LifecycleResponse, MaxRecipientsResponse, MinDepositResponse,
+    NormalizeAddressResponse, OverviewResponse, OwnerEarningsResponse, PreviewTransferResponse,
+    QueryMsg, SchemaVersionResponse, SenderFeeResponse, SenderFeesPaidResponse,
+    SenderTxCountResponse, ShareForResponse, SimulateTransferMsgResponse, StatsResponse,
+    StatusResponse, TransfersResponse, WithdrawableAtResponse, WithdrawalAddressResponse,
 };
 use cosmwasm_1_to_2_transfer::state::State;
 
@@ -29,4 +38,147 @@ fn main() {
         &out_dir,
         "GetSendFeeResponse",
     );
+    export_schema_with_title(
+        &schema_for!(ActiveDenomsResponse),
+        &out_dir,
+        "ActiveDenomsResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(PreviewTransferResponse),
+        &out_dir,
+        "PreviewTransferResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(AccountingResponse),
+        &out_dir,
+        "AccountingResponse",
+    );
+    #[cfg(feature = "debug-queries")]
+    export_schema_with_title(&schema_for!(State), &out_dir, "DumpStateResponse");
+    export_schema_with_title(
+        &schema_for!(LatestTransferResponse),
+        &out_dir,
+        "LatestTransferResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(TransfersResponse),
+        &out_dir,
+        "TransfersResponse",
+    );
+    export_schema_with_title(&schema_for!(FeePoolResponse), &out_dir, "FeePoolResponse");
+    export_schema_with_title(&schema_for!(ShareForResponse), &out_dir, "ShareForResponse");
+    export_schema_with_title(
+        &schema_for!(GetConfigResponse),
+        &out_dir,
+        "GetConfigResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(IsKnownAccountResponse),
+        &out_dir,
+        "IsKnownAccountResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(ConfigValueResponse),
+        &out_dir,
+        "ConfigValueResponse",
+    );
+    export_schema_with_title(&schema_for!(AccountsResponse), &out_dir, "AccountsResponse");
+    export_schema_with_title(&schema_for!(OverviewResponse), &out_dir, "OverviewResponse");
+    export_schema_with_title(
+        &schema_for!(SenderFeeResponse),
+        &out_dir,
+        "SenderFeeResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(WithdrawalAddressResponse),
+        &out_dir,
+        "WithdrawalAddressResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(FeeSnapshotsResponse),
+        &out_dir,
+        "FeeSnapshotsResponse",
+    );
+    export_schema_with_title(&schema_for!(StatusResponse), &out_dir, "StatusResponse");
+    export_schema_with_title(
+        &schema_for!(SenderTxCountResponse),
+        &out_dir,
+        "SenderTxCountResponse",
+    );
+    export_schema_with_title(&schema_for!(GroupResponse), &out_dir, "GroupResponse");
+    export_schema_with_title(
+        &schema_for!(NormalizeAddressResponse),
+        &out_dir,
+        "NormalizeAddressResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(SenderFeesPaidResponse),
+        &out_dir,
+        "SenderFeesPaidResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(DefaultPairResponse),
+        &out_dir,
+        "DefaultPairResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(HoldUntilResponse),
+        &out_dir,
+        "HoldUntilResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(MinDepositResponse),
+        &out_dir,
+        "MinDepositResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(ForwardingAddressResponse),
+        &out_dir,
+        "ForwardingAddressResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(LifecycleResponse),
+        &out_dir,
+        "LifecycleResponse",
+    );
+    export_schema_with_title(&schema_for!(FlagsResponse), &out_dir, "FlagsResponse");
+    export_schema_with_title(&schema_for!(IsEvenResponse), &out_dir, "IsEvenResponse");
+    export_schema_with_title(&schema_for!(IsAdminResponse), &out_dir, "IsAdminResponse");
+    export_schema_with_title(&schema_for!(StatsResponse), &out_dir, "StatsResponse");
+    export_schema_with_title(
+        &schema_for!(MaxRecipientsResponse),
+        &out_dir,
+        "MaxRecipientsResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(SchemaVersionResponse),
+        &out_dir,
+        "SchemaVersionResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(IsRegisteredResponse),
+        &out_dir,
+        "IsRegisteredResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(WithdrawableAtResponse),
+        &out_dir,
+        "WithdrawableAtResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(SimulateTransferMsgResponse),
+        &out_dir,
+        "SimulateTransferMsgResponse",
+    );
+    export_schema_with_title(
+        &schema_for!(OwnerEarningsResponse),
+        &out_dir,
+        "OwnerEarningsResponse",
+    );
+    export_schema_with_title(&schema_for!(HoldersResponse), &out_dir, "HoldersResponse");
+    export_schema_with_title(
+        &schema_for!(CounterDriftResponse),
+        &out_dir,
+        "CounterDriftResponse",
+    );
 }
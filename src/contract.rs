@@ -1,31 +1,202 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, to_binary, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
-    Uint128,
+    coins, to_json_binary, Addr, BankMsg, Binary, BlockInfo, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp, Uint128, Uint256,
 };
 use cw2::set_contract_version;
+use cw_storage_plus::{Bound, PrefixBound};
+use sha2::{Digest, Sha256};
+use std::collections::BinaryHeap;
 
 use crate::error::ContractError;
 use crate::msg::{
-    ExecuteMsg, GetBalanceResponse, GetOwnerResponse, GetSendFeeResponse, InstantiateMsg, QueryMsg,
+    AccountingResponse, AccountsResponse, ActiveDenomsResponse, BalanceEntry, BalancePreview,
+    ConfigValueResponse, CounterDriftResponse, DefaultPairResponse, ExecuteMsg, FeePoolResponse,
+    FeeSnapshot, FeeSnapshotsResponse, FlagsResponse, ForwardingAddressResponse,
+    GetBalanceResponse, GetConfigResponse, GetOwnerResponse, GetSendFeeResponse, GroupResponse,
+    HoldUntilResponse, Holder, HoldersResponse, InstantiateMsg, IsAdminResponse, IsEvenResponse,
+    IsKnownAccountResponse, IsRegisteredResponse, LatestTransferResponse, LifecycleResponse,
+    MaxRecipientsResponse, MinDepositResponse, NormalizeAddressResponse, OverviewResponse,
+    OwnerEarningsResponse, PreviewTransferResponse, QueryMsg, SchemaVersionResponse,
+    SenderFeeResponse, SenderFeesPaidResponse, SenderTxCountResponse, ShareForResponse,
+    SimulateTransferMsgResponse, SimulatedCredit, StatsResponse, Status, StatusResponse,
+    TransfersResponse, WeightedRecipient, WithdrawableAtResponse, WithdrawalAddressResponse,
+    MSG_VERSION,
+};
+use crate::state::{
+    Escrow, PauseState, PendingFee, State, TransferRecord, ACCOUNTS_BY_CATEGORY,
+    ACCOUNTS_BY_HEIGHT, ADMINS, BALANCES, BALANCE_CATEGORIES, CREATED_HEIGHT, CREDITED_BY,
+    DAILY_WITHDRAWN, DEFAULT_PAIRS, DENOM_MIGRATIONS, ESCROWS, FEES, FEE_SNAPSHOTS,
+    FORWARDING_ADDRESSES, FROZEN, GROUPS, HISTORY, HOLDS, LAST_ACTIVITY, LAST_TRANSFER,
+    LAST_WITHDRAW, LEGACY_BALANCES, LEGACY_TOTALS, RECEIVED_TOTAL, REGISTERED, SENDER_FEES,
+    SENDER_FEES_PAID, SENDER_TX_COUNT, STATE, TRANSFERS_BY_RECIPIENT, TRANSFERS_BY_SENDER,
+    WITHDRAWAL_ADDRESSES, WITHDRAW_LIMITS,
 };
-use crate::state::{State, BALANCES, STATE};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cosmwasm-1-to-2-transfer";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// pagination
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// longest note accepted on a withdrawal or memo on a transfer, in bytes
+const MAX_NOTE_LEN: usize = 256;
+
+// recipient count reported by `MaxRecipients` when no `max_recipients` is configured. a
+// conservative size for one transaction's gas, not a limit the contract enforces
+const DEFAULT_MAX_RECIPIENTS: u32 = 50;
+
+// longest category accepted on a transfer, in bytes
+const MAX_CATEGORY_LEN: usize = 64;
+
+// length of the window `SetWithdrawLimit` caps
+const SECONDS_PER_DAY: u64 = 86_400;
+
+// most accounts `SmallestHolders` returns
+const MAX_HOLDERS_LIMIT: u32 = 50;
+
+// most holders one `DistributePro` splits across
+const MAX_DISTRIBUTE_HOLDERS: u32 = 50;
+
+// most accounts `FreezeMany`, `UnfreezeMany` and `ClaimFor` accept at once
+const MAX_BATCH_ACCOUNTS: usize = 50;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    let owner = match msg.owner {
+        Some(owner) => deps
+            .api
+            .addr_validate(&owner)
+            .map_err(|_| ContractError::CustomError {
+                val: format!("invalid owner address {}", owner),
+            })?,
+        None => info.sender.clone(),
+    };
+    let fee_recipient = match msg.fee_recipient {
+        Some(fee_recipient) => {
+            deps.api
+                .addr_validate(&fee_recipient)
+                .map_err(|_| ContractError::CustomError {
+                    val: format!("invalid fee recipient address {}", fee_recipient),
+                })?
+        }
+        None => owner.clone(),
+    };
+    if matches!(msg.fee_bps, Some(bps) if bps > 10000) {
+        return Err(ContractError::CustomError {
+            val: "fee_bps must be at most 10000".into(),
+        });
+    }
+    if msg.repeat_window_secs > 0 && msg.repeat_fee_multiplier == 0 {
+        return Err(ContractError::CustomError {
+            val: "repeat_fee_multiplier must be at least 1 when repeat_window_secs is set".into(),
+        });
+    }
+    if matches!(msg.max_fee, Some(max_fee) if max_fee < msg.min_fee) {
+        return Err(ContractError::CustomError {
+            val: "min_fee must not be above max_fee".into(),
+        });
+    }
+    if msg.referral_bps > 10000 {
+        return Err(ContractError::CustomError {
+            val: "referral_bps must be at most 10000".into(),
+        });
+    }
+    if matches!(msg.max_fee_pct_bps, Some(bps) if bps > 10000) {
+        return Err(ContractError::CustomError {
+            val: "max_fee_pct_bps must be at most 10000".into(),
+        });
+    }
+    let max_fee_bound = msg.max_fee_bound.unwrap_or(Uint128::MAX);
+    if msg.min_fee_bound > max_fee_bound {
+        return Err(ContractError::CustomError {
+            val: "min_fee_bound must not be above max_fee_bound".into(),
+        });
+    }
+    if msg.send_fee < msg.min_fee_bound || msg.send_fee > max_fee_bound {
+        return Err(ContractError::CustomError {
+            val: "send_fee must be within the fee bounds".into(),
+        });
+    }
+    let denom_decimals = msg.denom_decimals.unwrap_or(6);
+    if denom_decimals > 18 {
+        return Err(ContractError::CustomError {
+            val: "denom_decimals must be at most 18".into(),
+        });
+    }
+    if let Some(fee_denom) = &msg.fee_denom {
+        if fee_denom == "usei" || fee_denom.is_empty() {
+            return Err(ContractError::CustomError {
+                val: "fee_denom must be a denom other than usei".into(),
+            });
+        }
+        // a percentage of usei can't be priced in another denom
+        if msg.fee_bps.is_some() {
+            return Err(ContractError::CustomError {
+                val: "fee_denom requires a flat send_fee".into(),
+            });
+        }
+    }
     let state = State {
-        owner: info.sender.clone(),
+        owner: owner.clone(),
+        fee_recipient,
+        strict_fee_recipient: msg.strict_fee_recipient,
         send_fee: msg.send_fee,
+        min_fee_bound: msg.min_fee_bound,
+        max_fee_bound,
+        fee_bps: msg.fee_bps,
+        min_fee: msg.min_fee,
+        max_fee: msg.max_fee,
+        rebate_every: msg.rebate_every,
+        round_fee_up: msg.round_fee_up,
+        max_fee_pct_bps: msg.max_fee_pct_bps,
+        referral_bps: msg.referral_bps,
+        min_share: msg.min_share,
+        fee_denom: msg.fee_denom,
+        reject_contract_recipients: msg.reject_contract_recipients,
+        require_registered_recipients: msg.require_registered_recipients,
+        allow_sender_as_recipient: msg.allow_sender_as_recipient.unwrap_or(true),
+        denom_decimals,
+        pause: PauseState::default(),
+        decommissioned: false,
+        instantiated_at: env.block.time,
+        decommissioned_at: None,
+        fee_holiday: false,
+        fee_to_recipients: msg.fee_to_recipients,
+        refund_accidental_funds: msg.refund_accidental_funds,
+        overpayment_refund: msg.overpayment_refund,
+        normalize_denom: msg.normalize_denom,
+        required_prefix: msg.required_prefix,
+        auto_withdraw: msg.auto_withdraw,
+        require_memo: msg.require_memo,
+        max_accounts: msg.max_accounts,
+        dust_threshold: msg.dust_threshold,
+        max_recipients: msg.max_recipients,
+        account_count: 0,
+        attr_prefix: None,
+        config_timelock_secs: msg.config_timelock_secs,
+        pending_fee: None,
+        max_withdraw_per_tx: msg.max_withdraw_per_tx,
+        withdraw_cooldown_secs: msg.withdraw_cooldown_secs,
+        repeat_window_secs: msg.repeat_window_secs,
+        repeat_fee_multiplier: msg.repeat_fee_multiplier,
+        inactivity_period_secs: msg.inactivity_period_secs,
+        verify_solvency_on_withdraw: msg.verify_solvency_on_withdraw,
+        auto_pause_on_insolvency: msg.auto_pause_on_insolvency,
+        total_outstanding: Uint128::zero(),
+        fee_pool: Uint128::zero(),
+        total_fees_collected: Uint128::zero(),
+        transfer_count: 0,
+        total_escrowed: Uint128::zero(),
+        escrow_count: 0,
     };
     if !info.funds.is_empty() {
         return Err(ContractError::CustomError {
@@ -37,14 +208,34 @@ pub fn instantiate(
 
     Ok(Response::new()
         .add_attribute("action", "instantiate")
-        .add_attribute("owner", info.sender)
-        .add_attribute("send_fee", msg.send_fee.to_string()))
+        .add_attribute("owner", owner)
+        .add_attribute("send_fee", msg.send_fee.to_string())
+        .add_attribute(
+            "withdraw_cooldown_secs",
+            msg.withdraw_cooldown_secs.to_string(),
+        ))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let mut res = dispatch(deps.branch(), env, info, msg)?;
+    // loaded after dispatch so a prefix change applies to its own response
+    if let Some(prefix) = STATE.load(deps.storage)?.attr_prefix {
+        for attr in res.attributes.iter_mut() {
+            attr.key = format!("{}_{}", prefix, attr.key);
+        }
+    }
+    Ok(res)
+}
+
+fn dispatch(
     deps: DepsMut,
-    _env: Env, // mostly used for block height at this point
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
@@ -52,472 +243,9800 @@ pub fn execute(
         ExecuteMsg::Transfer {
             recipient_a,
             recipient_b,
-        } => execute_transfer(deps, info, &recipient_a, &recipient_b),
-        ExecuteMsg::Withdraw { amount } => execute_withdraw(deps, info, amount),
+            category,
+        } => transfer_pair(deps, env, info, &recipient_a, &recipient_b, None, category),
+        ExecuteMsg::TransferWithMemo {
+            recipient_a,
+            recipient_b,
+            memo,
+        } => transfer_pair(
+            deps,
+            env,
+            info,
+            &recipient_a,
+            &recipient_b,
+            Some(memo),
+            None,
+        ),
+        ExecuteMsg::TransferWithReferral {
+            recipient_a,
+            recipient_b,
+            referrer,
+        } => execute_transfer_with_referral(deps, env, info, &recipient_a, &recipient_b, &referrer),
+        ExecuteMsg::TransferWithEscrow {
+            recipient_a,
+            recipient_b,
+            release_code_hash,
+        } => execute_transfer_with_escrow(
+            deps,
+            env,
+            info,
+            &recipient_a,
+            &recipient_b,
+            release_code_hash,
+        ),
+        ExecuteMsg::ReleaseEscrow {
+            escrow_id,
+            preimage,
+        } => execute_release_escrow(deps, env, info, escrow_id, preimage),
+        ExecuteMsg::SetDefaultPair {
+            recipient_a,
+            recipient_b,
+        } => execute_set_default_pair(deps, info, &recipient_a, &recipient_b),
+        ExecuteMsg::TransferToMyDefault {} => execute_transfer_to_my_default(deps, env, info),
+        ExecuteMsg::TransferToGroup { name } => execute_transfer_to_group(deps, env, info, &name),
+        ExecuteMsg::TransferWeighted { recipients } => {
+            execute_transfer_weighted(deps, env, info, recipients)
+        }
+        ExecuteMsg::DistributePro { start_after, limit } => {
+            execute_distribute_pro(deps, env, info, start_after, limit)
+        }
+        ExecuteMsg::Withdraw { amount, note } => {
+            execute_withdraw_with_note(deps, env, info, amount, note)
+        }
+        ExecuteMsg::WithdrawAndTransfer {
+            amount,
+            recipient_a,
+            recipient_b,
+        } => execute_withdraw_and_transfer(deps, env, info, amount, &recipient_a, &recipient_b),
+        ExecuteMsg::ForwardSplit {
+            recipient_a,
+            recipient_b,
+        } => execute_forward_split(deps, env, info, &recipient_a, &recipient_b),
+        ExecuteMsg::WithdrawPercent { bps } => execute_withdraw_percent(deps, env, info, bps),
+        ExecuteMsg::WithdrawAll {} => execute_withdraw_all(deps, env, info),
+        ExecuteMsg::SetWithdrawalAddress { addr } => {
+            execute_set_withdrawal_address(deps, info, &addr)
+        }
+        ExecuteMsg::SetForwardingAddress { addr } => {
+            execute_set_forwarding_address(deps, info, &addr)
+        }
+        ExecuteMsg::ClearForwarding {} => execute_clear_forwarding(deps, info),
+        ExecuteMsg::Register {} => execute_register(deps, info),
+        ExecuteMsg::Reject {} => execute_reject(deps, env, info),
+        ExecuteMsg::SeizeAccount { account, reason } => {
+            execute_seize_account(deps, info, &account, reason)
+        }
+        ExecuteMsg::FreezeMany { accounts } => execute_set_frozen(deps, info, accounts, true),
+        ExecuteMsg::UnfreezeMany { accounts } => execute_set_frozen(deps, info, accounts, false),
+        ExecuteMsg::SetWithdrawLimit { account, limit } => {
+            execute_set_withdraw_limit(deps, info, &account, limit)
+        }
+        ExecuteMsg::ReclaimInactive { account } => {
+            execute_reclaim_inactive(deps, env, info, &account)
+        }
+        ExecuteMsg::HoldAccount { account, until } => {
+            execute_hold_account(deps, info, &account, until)
+        }
+        ExecuteMsg::Reassign { from, to } => execute_reassign(deps, env, info, &from, &to),
+        ExecuteMsg::Handover {
+            new_owner,
+            new_fee_recipient,
+        } => execute_handover(deps, info, &new_owner, &new_fee_recipient),
+        ExecuteMsg::AddAdmin { addr } => execute_set_admin(deps, info, &addr, true),
+        ExecuteMsg::RemoveAdmin { addr } => execute_set_admin(deps, info, &addr, false),
+        ExecuteMsg::SetPause {
+            transfers,
+            withdrawals,
+        } => execute_set_pause(deps, info, transfers, withdrawals),
+        ExecuteMsg::SetFeeHoliday { enabled } => execute_set_fee_holiday(deps, info, enabled),
+        ExecuteMsg::UpdateSendFee { fee } => execute_update_send_fee(deps, env, info, fee),
+        ExecuteMsg::ApplyPending {} => execute_apply_pending(deps, env),
+        ExecuteMsg::FundFeePool {} => execute_fund_fee_pool(deps, info),
+        ExecuteMsg::ClaimFees { amount } => execute_claim_fees(deps, info, amount),
+        ExecuteMsg::SetSenderFee { sender, fee } => {
+            execute_set_sender_fee(deps, info, &sender, Some(fee))
+        }
+        ExecuteMsg::SetDenomFee { denom, fee } => execute_set_denom_fee(deps, info, denom, fee),
+        ExecuteMsg::ClearSenderFee { sender } => execute_set_sender_fee(deps, info, &sender, None),
+        ExecuteMsg::Decommission {} => execute_decommission(deps, env, info),
+        ExecuteMsg::RefundAll { limit } => execute_refund_all(deps, env, info, limit),
+        ExecuteMsg::SetMaxAccounts { max_accounts } => {
+            execute_set_max_accounts(deps, info, max_accounts)
+        }
+        ExecuteMsg::ReconcileCounters {} => execute_reconcile_counters(deps, info),
+        ExecuteMsg::SetRequireMemo { required } => execute_set_require_memo(deps, info, required),
+        ExecuteMsg::SetAttrPrefix { prefix } => execute_set_attr_prefix(deps, info, prefix),
+        ExecuteMsg::RescueToken { denom, recipient } => {
+            execute_rescue_token(deps, env, info, denom, &recipient)
+        }
+        ExecuteMsg::SnapshotFees {} => execute_snapshot_fees(deps, env, info),
+        ExecuteMsg::ImportBalances { entries, denom } => {
+            execute_import_balances(deps, env, info, entries, denom)
+        }
+        ExecuteMsg::SetDenomMigration {
+            from_denom,
+            to_denom,
+            rate,
+        } => execute_set_denom_migration(deps, info, from_denom, to_denom, rate),
+        ExecuteMsg::Consolidate {} => execute_consolidate(deps, env, info),
+        ExecuteMsg::ClaimFor { accounts } => execute_claim_for(deps, env, info, accounts),
+        ExecuteMsg::SetGroup { name, members } => execute_set_group(deps, info, name, members),
     }
 }
 
 pub fn execute_transfer(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     recipient_a: &str,
     recipient_b: &str,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
+    transfer_pair(deps, env, info, recipient_a, recipient_b, None, None)
+}
 
-    // validate funds: should be a vector with one element: the usei coin
-    if info.funds.is_empty() {
+fn transfer_pair(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient_a: &str,
+    recipient_b: &str,
+    memo: Option<String>,
+    category: Option<String>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.pause.transfers {
         return Err(ContractError::CustomError {
-            val: "please send usei".into(),
+            val: "transfers are paused".into(),
         });
     }
-
-    if info.funds.len() != 1 {
+    // an empty memo doesn't count
+    let memo = memo.filter(|memo| !memo.is_empty());
+    match &memo {
+        None if state.require_memo => return Err(ContractError::MemoRequired {}),
+        Some(memo) if memo.len() > MAX_NOTE_LEN => {
+            return Err(ContractError::CustomError {
+                val: format!("memo must be at most {} bytes", MAX_NOTE_LEN),
+            })
+        }
+        _ => {}
+    }
+    // nor does an empty category, so it can't be indexed under ""
+    let category = category.filter(|category| !category.is_empty());
+    if matches!(&category, Some(category) if category.len() > MAX_CATEGORY_LEN) {
         return Err(ContractError::CustomError {
-            val: "please only send usei".into(),
+            val: format!("category must be at most {} bytes", MAX_CATEGORY_LEN),
         });
     }
+    let funds = transfer_funds(deps.as_ref(), &state, &info)?;
+    let fee_override = fee_override(deps.storage, &state, Some(&info.sender))?;
+    let min_deposit = min_deposit(&state, fee_override);
+    if funds < min_deposit {
+        return Err(ContractError::DepositTooSmall { funds, min_deposit });
+    }
 
-    let funds = if info.funds[0].denom == "usei" {
-        info.funds[0].amount
-    } else {
-        return Err(ContractError::CustomError {
-            val: format!(
-                "invalid denomination {}. please send usei",
-                info.funds[0].denom
-            ),
-        });
-    };
+    let (res, half) = split_and_credit(
+        deps.branch(),
+        &env,
+        &info.sender,
+        state,
+        funds,
+        &[recipient_a, recipient_b],
+        None,
+        None,
+        false,
+        "transfer",
+    )?;
 
-    // ensure balance (minus the transfer fee) is even (instructions say to divide money evenly. requires an even number) and nonzero
-    if funds <= state.send_fee {
-        return Err(ContractError::CustomError {
-            val: "funds <= fee".into(),
-        });
+    if let Some(category) = &category {
+        for recipient in [recipient_a, recipient_b] {
+            let addr = forwarded(deps.storage, deps.api.addr_validate(recipient)?)?;
+            // nothing is credited in auto-withdraw mode
+            if BALANCES.has(deps.storage, addr.clone()) {
+                set_category(deps.storage, &addr, category)?;
+            }
+        }
     }
 
-    // ensure the funds can be divided evenly
-    // to_send is guaranteed to be nonzero
-    let to_send = funds - state.send_fee;
-    if to_send % Uint128::from(2u32) != Uint128::from(0u32) {
-        return Err(ContractError::CustomError {
-            val: format!(
-                "invalid funds. please send an even number of usei + a fee of {}",
-                state.send_fee
-            ),
-        });
+    // balances after crediting, so clients don't have to query them. a recipient listed twice
+    // reports its merged balance in both
+    let balance_of = |addr: &str| -> StdResult<Uint128> {
+        let addr = forwarded(deps.storage, deps.api.addr_validate(addr)?)?;
+        Ok(BALANCES.may_load(deps.storage, addr)?.unwrap_or_default())
+    };
+    let res = res
+        .add_attribute("recipient_a", half)
+        .add_attribute("recipient_b", half)
+        .add_attribute("recipient_a_balance", balance_of(recipient_a)?)
+        .add_attribute("recipient_b_balance", balance_of(recipient_b)?);
+    let res = match memo {
+        Some(memo) => res.add_attribute("memo", memo),
+        None => res,
+    };
+    Ok(match category {
+        Some(category) => res.add_attribute("category", category),
+        None => res,
+    })
+}
+
+/// for the transfer variants that can't carry a memo
+fn ensure_memo_not_required(state: &State) -> Result<(), ContractError> {
+    if state.require_memo {
+        return Err(ContractError::MemoRequired {});
     }
+    Ok(())
+}
 
-    // calculate the amount to give to each account
-    // half is guaranteed to be nonzero
-    let half = to_send / Uint128::from(2u32);
+/// remembers who credited `addr` so `Reject` can return it. call before crediting. a balance
+/// credited by several senders can't be returned to any single one of them
+fn record_credited_by(storage: &mut dyn Storage, addr: &Addr, sender: &Addr) -> StdResult<()> {
+    if !BALANCES.has(storage, addr.clone()) {
+        return CREDITED_BY.save(storage, addr.clone(), &Some(sender.clone()));
+    }
+    let credited_by = CREDITED_BY.may_load(storage, addr.clone())?.flatten();
+    if credited_by.as_ref() != Some(sender) {
+        CREDITED_BY.save(storage, addr.clone(), &None)?;
+    }
+    Ok(())
+}
 
-    // create accounts if not exist and credit accounts
-    // can only move DepsMut once so have to do this in a loop :(
-    let accounts = vec![recipient_a, recipient_b];
-    for account in accounts {
-        let addr = deps.api.addr_validate(account)?;
-        if !BALANCES.has(deps.storage, addr.clone()) {
-            BALANCES.save(deps.storage, addr, &half)?;
-        } else {
-            let balance = BALANCES.load(deps.storage, addr.clone())?;
-            let new_balance = match Uint128::checked_add(balance, half) {
-                Ok(r) => r,
-                Err(_) => {
-                    return Err(ContractError::CustomError {
-                        val: "balance overflow occured".into(),
-                    })
-                }
-            };
+/// tags the balance of `addr` with `category`, replacing its previous one
+fn set_category(storage: &mut dyn Storage, addr: &Addr, category: &str) -> StdResult<()> {
+    if let Some(previous) = BALANCE_CATEGORIES.may_load(storage, addr.clone())? {
+        ACCOUNTS_BY_CATEGORY.remove(storage, (previous, addr.clone()));
+    }
+    BALANCE_CATEGORIES.save(storage, addr.clone(), &category.to_string())?;
+    ACCOUNTS_BY_CATEGORY.save(storage, (category.to_string(), addr.clone()), &())
+}
 
-            BALANCES.save(deps.storage, addr, &new_balance)?;
-        }
+pub fn execute_transfer_with_referral(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient_a: &str,
+    recipient_b: &str,
+    referrer: &str,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.pause.transfers {
+        return Err(ContractError::CustomError {
+            val: "transfers are paused".into(),
+        });
     }
+    ensure_memo_not_required(&state)?;
+    let funds = transfer_funds(deps.as_ref(), &state, &info)?;
 
-    // send fee
-    let mut res = Response::new();
-    res = res
-        .add_message(BankMsg::Send {
-            to_address: state.owner.into(),
-            amount: coins(state.send_fee.u128(), "usei"),
-        })
-        .add_attribute("action", "transfer")
+    let (res, half) = split_and_credit(
+        deps,
+        &env,
+        &info.sender,
+        state,
+        funds,
+        &[recipient_a, recipient_b],
+        Some(referrer),
+        None,
+        false,
+        "transfer_with_referral",
+    )?;
+    Ok(res
+        .add_attribute("referrer", referrer)
         .add_attribute("recipient_a", half)
-        .add_attribute("recipient_b", half);
-    Ok(res)
+        .add_attribute("recipient_b", half))
 }
 
-pub fn execute_withdraw(
-    deps: DepsMut,
+pub fn execute_transfer_with_escrow(
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    amount: Uint128,
+    recipient_a: &str,
+    recipient_b: &str,
+    release_code_hash: Binary,
 ) -> Result<Response, ContractError> {
-    if !info.funds.is_empty() {
+    let state = STATE.load(deps.storage)?;
+    if state.pause.transfers {
         return Err(ContractError::CustomError {
-            val: "no funds required".into(),
+            val: "transfers are paused".into(),
         });
     }
-    // ensure account exists
-    if !BALANCES.has(deps.storage, info.sender.clone()) {
-        return Err(ContractError::Unauthorized {});
+    ensure_memo_not_required(&state)?;
+    // the share has to sit in a balance before it can be moved into escrow
+    if state.auto_withdraw {
+        return Err(ContractError::CustomError {
+            val: "escrow isn't available in auto-withdraw mode".into(),
+        });
     }
-    // check balance
-    let balance = BALANCES.load(deps.storage, info.sender.clone())?;
-    if amount > balance {
+    if release_code_hash.len() != 32 {
         return Err(ContractError::CustomError {
-            val: "insufficient funds".into(),
+            val: "release_code_hash must be a sha256 hash".into(),
         });
     }
+    let funds = transfer_funds(deps.as_ref(), &state, &info)?;
 
-    // deduct balance
-    let new_balance = balance - amount;
+    let (res, half) = split_and_credit(
+        deps.branch(),
+        &env,
+        &info.sender,
+        state,
+        funds,
+        &[recipient_a, recipient_b],
+        None,
+        None,
+        false,
+        "transfer_with_escrow",
+    )?;
 
-    // delete empty balance
-    if new_balance == Uint128::from(0u32) {
-        BALANCES.remove(deps.storage, info.sender.clone());
-    } else {
-        BALANCES.save(deps.storage, info.sender.clone(), &new_balance)?;
+    // move recipient_b's share out of its balance and into escrow
+    let recipient = forwarded(deps.storage, deps.api.addr_validate(recipient_b)?)?;
+    let mut state = STATE.load(deps.storage)?;
+    debit_balance(deps.storage, &mut state, &recipient, half)?;
+    let escrow_id = state.escrow_count;
+    ESCROWS.save(
+        deps.storage,
+        escrow_id,
+        &Escrow {
+            sender: info.sender,
+            recipient,
+            amount: half,
+            release_code_hash,
+        },
+    )?;
+    state.escrow_count += 1;
+    state.total_escrowed = state.total_escrowed.checked_add(half)?;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(res
+        .add_attribute("recipient_a", half)
+        .add_attribute("escrowed", half)
+        .add_attribute("escrow_id", escrow_id.to_string()))
+}
+
+/// credits an escrowed share to its recipient. anyone who knows the preimage can release it
+pub fn execute_release_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_id: u64,
+    preimage: Binary,
+) -> Result<Response, ContractError> {
+    ensure_no_funds(&info)?;
+    let escrow = match ESCROWS.may_load(deps.storage, escrow_id)? {
+        Some(escrow) => escrow,
+        None => {
+            return Err(ContractError::CustomError {
+                val: format!("unknown escrow {}", escrow_id),
+            })
+        }
+    };
+    if Sha256::digest(preimage.as_slice())[..] != escrow.release_code_hash[..] {
+        return Err(ContractError::CustomError {
+            val: "the preimage doesn't match the release code hash".into(),
+        });
     }
 
-    // send coins
-    let mut res = Response::new();
-    res = res.add_message(BankMsg::Send {
-        to_address: info.sender.into(),
-        amount: coins(amount.u128(), "usei"),
-    });
+    let mut state = STATE.load(deps.storage)?;
+    ESCROWS.remove(deps.storage, escrow_id);
+    state.total_escrowed = state.total_escrowed.saturating_sub(escrow.amount);
+    record_credited_by(deps.storage, &escrow.recipient, &escrow.sender)?;
+    credit_balance(
+        deps.storage,
+        &mut state,
+        &escrow.recipient,
+        escrow.amount,
+        &env.block,
+    )?;
+    STATE.save(deps.storage, &state)?;
 
-    Ok(res.add_attribute("action", "withdraw"))
+    Ok(Response::new()
+        .add_attribute("action", "release_escrow")
+        .add_attribute("escrow_id", escrow_id.to_string())
+        .add_attribute("recipient", escrow.recipient)
+        .add_attribute("amount", escrow.amount))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetOwner {} => to_binary(&query_owner(deps)?),
-        QueryMsg::GetSendFee {} => to_binary(&query_send_fee(deps)?),
-        QueryMsg::GetBalance { account } => to_binary(&query_balance(deps, &account)?),
-    }
+pub fn execute_set_default_pair(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient_a: &str,
+    recipient_b: &str,
+) -> Result<Response, ContractError> {
+    ensure_no_funds(&info)?;
+    let recipient_a = deps.api.addr_validate(recipient_a)?;
+    let recipient_b = deps.api.addr_validate(recipient_b)?;
+    DEFAULT_PAIRS.save(
+        deps.storage,
+        info.sender,
+        &(recipient_a.clone(), recipient_b.clone()),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_default_pair")
+        .add_attribute("recipient_a", recipient_a)
+        .add_attribute("recipient_b", recipient_b))
 }
 
-fn query_owner(deps: Deps) -> StdResult<GetOwnerResponse> {
-    let state = STATE.load(deps.storage)?;
-    Ok(GetOwnerResponse { owner: state.owner })
+pub fn execute_transfer_to_my_default(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let (recipient_a, recipient_b) =
+        match DEFAULT_PAIRS.may_load(deps.storage, info.sender.clone())? {
+            Some(pair) => pair,
+            None => {
+                return Err(ContractError::CustomError {
+                    val: "no default pair set".into(),
+                })
+            }
+        };
+    execute_transfer(deps, env, info, recipient_a.as_str(), recipient_b.as_str())
 }
 
-fn query_send_fee(deps: Deps) -> StdResult<GetSendFeeResponse> {
+pub fn execute_transfer_to_group(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: &str,
+) -> Result<Response, ContractError> {
     let state = STATE.load(deps.storage)?;
-    Ok(GetSendFeeResponse {
-        fee: state.send_fee,
-    })
+    if state.pause.transfers {
+        return Err(ContractError::CustomError {
+            val: "transfers are paused".into(),
+        });
+    }
+    ensure_memo_not_required(&state)?;
+    let members = match GROUPS.may_load(deps.storage, name.to_string())? {
+        Some(members) if !members.is_empty() => members,
+        Some(_) => {
+            return Err(ContractError::CustomError {
+                val: format!("group {} has no members", name),
+            })
+        }
+        None => {
+            return Err(ContractError::CustomError {
+                val: format!("unknown group {}", name),
+            })
+        }
+    };
+    let funds = transfer_funds(deps.as_ref(), &state, &info)?;
+
+    let members: Vec<&str> = members.iter().map(Addr::as_str).collect();
+    let (res, share) = split_and_credit(
+        deps,
+        &env,
+        &info.sender,
+        state,
+        funds,
+        &members,
+        None,
+        None,
+        false,
+        "transfer_to_group",
+    )?;
+    Ok(res
+        .add_attribute("group", name)
+        .add_attribute("share", share))
 }
 
-fn query_balance(deps: Deps, account: &str) -> StdResult<GetBalanceResponse> {
-    let addr = deps.api.addr_validate(account)?;
+pub fn execute_transfer_weighted(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipients: Vec<WeightedRecipient>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.pause.transfers {
+        return Err(ContractError::CustomError {
+            val: "transfers are paused".into(),
+        });
+    }
+    ensure_memo_not_required(&state)?;
+    if recipients.is_empty() || recipients.iter().any(|r| r.weight == 0) {
+        return Err(ContractError::CustomError {
+            val: "a weighted transfer needs recipients with nonzero weights".into(),
+        });
+    }
+    let funds = transfer_funds(deps.as_ref(), &state, &info)?;
 
-    let balance = if BALANCES.has(deps.storage, addr.clone()) {
-        // returns error if key isn't present. have to check `has` first
-        BALANCES.load(deps.storage, addr)?
-    } else {
-        Uint128::from(0u32)
-    };
-    Ok(GetBalanceResponse { balance })
+    let addrs: Vec<&str> = recipients.iter().map(|r| r.addr.as_str()).collect();
+    let weights: Vec<u128> = recipients.iter().map(|r| r.weight.into()).collect();
+    let (res, _) = split_and_credit(
+        deps,
+        &env,
+        &info.sender,
+        state,
+        funds,
+        &addrs,
+        None,
+        Some(&weights),
+        false,
+        "transfer_weighted",
+    )?;
+    Ok(res)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coin, coins, from_binary, Addr, CosmosMsg};
+pub fn execute_distribute_pro(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.pause.transfers {
+        return Err(ContractError::CustomError {
+            val: "transfers are paused".into(),
+        });
+    }
+    ensure_memo_not_required(&state)?;
+    let funds = transfer_funds(deps.as_ref(), &state, &info)?;
+    let (holders, last_holder) = distribution_holders(
+        deps.as_ref(),
+        &state,
+        Some(&info.sender),
+        start_after,
+        limit,
+    )?;
 
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies();
+    let addrs: Vec<&str> = holders.iter().map(|(addr, _)| addr.as_str()).collect();
+    let weights: Vec<u128> = holders.iter().map(|(_, balance)| balance.u128()).collect();
+    let (res, _) = split_and_credit(
+        deps,
+        &env,
+        &info.sender,
+        state,
+        funds,
+        &addrs,
+        None,
+        Some(&weights),
+        true,
+        "distribute_pro",
+    )?;
+    Ok(res
+        .add_attribute("holders", holders.len().to_string())
+        .add_attribute("last_holder", last_holder))
+}
 
-        let msg = InstantiateMsg {
-            send_fee: Uint128::from(1u32),
-        };
+/// the page of balance holders `DistributePro` splits across, and the last holder on the page to
+/// continue after. holders that can't be credited are left out rather than failing the whole
+/// distribution
+fn distribution_holders(
+    deps: Deps,
+    state: &State,
+    sender: Option<&Addr>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<(Vec<(Addr, Uint128)>, Addr), ContractError> {
+    let limit = limit
+        .unwrap_or(MAX_DISTRIBUTE_HOLDERS)
+        .min(MAX_DISTRIBUTE_HOLDERS)
+        .min(state.max_recipients.unwrap_or(u32::MAX)) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .map(Bound::exclusive);
+    let page = BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let last_holder = match page.last() {
+        Some((addr, _)) => addr.clone(),
+        None => {
+            return Err(ContractError::CustomError {
+                val: "there are no balance holders to distribute to".into(),
+            })
+        }
+    };
+    let mut holders = vec![];
+    for (addr, balance) in page {
+        let credited = forwarded(deps.storage, addr.clone())?;
+        if check_recipient(deps, state, sender, &credited).is_ok() {
+            holders.push((addr, balance));
+        }
+    }
+    if holders.is_empty() {
+        return Err(ContractError::CustomError {
+            val: "none of these balance holders can be distributed to".into(),
+        });
+    }
+    Ok((holders, last_holder))
+}
 
-        // negative path - initializing the contract with money
+/// validates the coins attached to a transfer and returns the usei amount to split. when the fee
+/// is paid in `fee_denom` the exact fee has to be attached alongside the usei
+fn transfer_funds(deps: Deps, state: &State, info: &MessageInfo) -> Result<Uint128, ContractError> {
+    let normalized;
+    let info = if state.normalize_denom {
+        normalized = lowercase_denoms(info);
+        &normalized
+    } else {
+        info
+    };
+    let fee_denom = match &state.fee_denom {
+        // validate funds: should be a vector with one element: the usei coin
+        None => return ensure_single_denom(info, "usei"),
+        Some(fee_denom) => fee_denom,
+    };
+
+    // validate funds: should be the usei coin and the fee coin
+    let mut funds = Uint128::zero();
+    let mut fee_paid = Uint128::zero();
+    for coin in &info.funds {
+        if coin.denom == "usei" {
+            funds = coin.amount;
+        } else if &coin.denom == fee_denom {
+            fee_paid = coin.amount;
+        } else {
+            return Err(ContractError::CustomError {
+                val: format!(
+                    "invalid denomination {}. please send usei and the fee in {}",
+                    coin.denom, fee_denom
+                ),
+            });
+        }
+    }
+    if funds == Uint128::from(0u32) {
+        return Err(ContractError::CustomError {
+            val: "please send usei".into(),
+        });
+    }
+    let fee_override = fee_override(deps.storage, state, Some(&info.sender))?;
+    let fee = compute_fee(state, funds, fee_override);
+    if fee_paid != fee {
+        return Err(ContractError::CustomError {
+            val: format!("please send a fee of {}{}", fee, fee_denom),
+        });
+    }
+    Ok(funds)
+}
+
+/// takes the fee out of `funds` and credits an equal share of the rest to each recipient, or pays
+/// it out directly in auto-withdraw mode. with a `referrer`, `referral_bps` of what's left after
+/// the fee goes to the referrer before the split. with `weights`, one per recipient, the rest is
+/// divided in proportion to them instead, and `drop_small` refunds shares that round too small
+/// instead of failing. returns the response and the share, which is the whole amount split for
+/// weighted transfers. `funds` must already be accounted for by the caller. only
+/// equal transfers between exactly two recipients are recorded in `HISTORY`
+#[allow(clippy::too_many_arguments)]
+fn split_and_credit(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    mut state: State,
+    funds: Uint128,
+    recipients: &[&str],
+    referrer: Option<&str>,
+    weights: Option<&[u128]>,
+    drop_small: bool,
+    action: &str,
+) -> Result<(Response, Uint128), ContractError> {
+    if state.decommissioned {
+        return Err(ContractError::CustomError {
+            val: "the contract is decommissioned".into(),
+        });
+    }
+    if let Some(max_recipients) = state.max_recipients {
+        if recipients.len() > max_recipients as usize {
+            return Err(ContractError::CustomError {
+                val: format!("a transfer can have at most {} recipients", max_recipients),
+            });
+        }
+    }
+    let mut fee_override = fee_override(deps.storage, &state, Some(sender))?;
+    let escalated = escalate_fee(deps.storage, &state, env, sender, funds, &mut fee_override)?;
+    let redistributed = waive_fee(&state, funds, &mut fee_override);
+    let mut referral = None;
+    if let Some(referrer) = referrer {
+        let referrer = forwarded(deps.storage, deps.api.addr_validate(referrer)?)?;
+        if referrer == *sender {
+            return Err(ContractError::CustomError {
+                val: "the sender can't refer itself".into(),
+            });
+        }
+        let (fee, bonus) = referral_bonus(&state, funds, fee_override);
+        fee_override = Some(fee);
+        if !bonus.is_zero() {
+            referral = Some((referrer, bonus));
+        }
+    }
+    let bonus = referral
+        .as_ref()
+        .map(|(_, bonus)| *bonus)
+        .unwrap_or_default();
+    let (fee, share, shares, dust) = plan_split(
+        &state,
+        funds - bonus,
+        fee_override,
+        recipients.len(),
+        weights,
+        drop_small,
+    )?;
+
+    // create accounts if not exist and credit accounts
+    // can only move DepsMut once so have to do this in a loop :(
+    let recipients = recipients
+        .iter()
+        .map(|addr| forwarded(deps.storage, deps.api.addr_validate(addr)?))
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut credits: Vec<(Addr, Uint128)> = recipients.iter().cloned().zip(shares).collect();
+    credits.extend(referral);
+    // dropped shares are already in the dust
+    credits.retain(|(_, amount)| !amount.is_zero());
+    let mut credit_types = vec![];
+    for (addr, amount) in &credits {
+        check_recipient(deps.as_ref(), &state, Some(sender), addr)?;
+        RECEIVED_TOTAL.update(deps.storage, addr.clone(), |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default().checked_add(*amount)?)
+        })?;
+        // in auto-withdraw mode the shares are paid out below instead of credited
+        if state.auto_withdraw {
+            continue;
+        }
+        if !BALANCES.has(deps.storage, addr.clone()) {
+            credit_types.push("new");
+        } else {
+            credit_types.push("topup");
+        }
+        record_credited_by(deps.storage, addr, sender)?;
+        credit_balance(deps.storage, &mut state, addr, *amount, &env.block)?;
+    }
+
+    // record the transfer
+    if let (None, [recipient_a, recipient_b]) = (weights, recipients.as_slice()) {
+        let id = state.transfer_count;
+        HISTORY.save(
+            deps.storage,
+            id,
+            &TransferRecord {
+                id,
+                sender: sender.clone(),
+                recipient_a: recipient_a.clone(),
+                recipient_b: recipient_b.clone(),
+                share,
+                fee,
+                block_height: env.block.height,
+            },
+        )?;
+        TRANSFERS_BY_SENDER.save(deps.storage, (sender.clone(), id), &())?;
+        TRANSFERS_BY_RECIPIENT.save(deps.storage, (recipient_a.clone(), id), &())?;
+        TRANSFERS_BY_RECIPIENT.save(deps.storage, (recipient_b.clone(), id), &())?;
+        state.transfer_count += 1;
+    }
+    let sender_tx_count = SENDER_TX_COUNT
+        .may_load(deps.storage, sender.clone())?
+        .unwrap_or_default()
+        + 1;
+    SENDER_TX_COUNT.save(deps.storage, sender.clone(), &sender_tx_count)?;
+    if state.repeat_window_secs > 0 {
+        LAST_TRANSFER.save(deps.storage, sender.clone(), &env.block.time)?;
+    }
+    // a rebated fee goes back to the sender and isn't counted as collected
+    let rebate = state.rebate_every > 0 && sender_tx_count % state.rebate_every == 0;
+    if !rebate {
+        state.total_fees_collected = state.total_fees_collected.checked_add(fee)?;
+        SENDER_FEES_PAID.update(deps.storage, sender.clone(), |paid| -> StdResult<_> {
+            Ok(paid.unwrap_or_default().checked_add(fee)?)
+        })?;
+    }
+    STATE.save(deps.storage, &state)?;
+
+    // send fee. a percentage fee can round down to nothing, and the bank module rejects empty sends
+    let mut res = Response::new();
+    if fee > Uint128::from(0u32) {
+        let fee_denom = state.fee_denom.unwrap_or_else(|| "usei".to_string());
+        let to_address = if rebate {
+            res = res.add_attribute("rebate", fee);
+            sender.to_string()
+        } else {
+            state.fee_recipient.to_string()
+        };
+        res = res.add_message(BankMsg::Send {
+            to_address,
+            amount: coins(fee.u128(), fee_denom),
+        });
+    }
+    if !dust.is_zero() {
+        res = res
+            .add_attribute("dust_refund", dust)
+            .add_message(BankMsg::Send {
+                to_address: sender.to_string(),
+                amount: coins(dust.u128(), "usei"),
+            });
+    }
+    if state.auto_withdraw {
+        // a recipient listed more than once gets a single send for all of its shares
+        let mut payouts: Vec<(Addr, Uint128)> = vec![];
+        for (recipient, credit) in credits {
+            match payouts.iter_mut().find(|(addr, _)| *addr == recipient) {
+                Some((_, amount)) => *amount += credit,
+                None => payouts.push((recipient, credit)),
+            }
+        }
+        for (recipient, amount) in payouts {
+            res = res.add_message(BankMsg::Send {
+                to_address: recipient.into(),
+                amount: coins(amount.u128(), "usei"),
+            });
+        }
+    }
+    res = res.add_attribute("action", action);
+    if escalated {
+        res = res.add_attribute("escalated_fee", fee);
+    }
+    if !redistributed.is_zero() {
+        res = res.add_attribute("fee_to_recipients", redistributed);
+    }
+    // one per credited recipient, in order. nothing is credited in auto-withdraw mode
+    for credit_type in credit_types {
+        res = res.add_attribute("credit_type", credit_type);
+    }
+    Ok((res, share))
+}
+
+/// rejects recipients the configuration doesn't allow to be credited. without a `sender` the
+/// sender-as-recipient rule can't be checked and is skipped
+fn check_recipient(
+    deps: Deps,
+    state: &State,
+    sender: Option<&Addr>,
+    addr: &Addr,
+) -> Result<(), ContractError> {
+    check_prefix(state, addr)?;
+    if state.strict_fee_recipient && *addr == state.fee_recipient {
+        return Err(ContractError::CustomError {
+            val: "the fee recipient can't be a transfer recipient".into(),
+        });
+    }
+    if !state.allow_sender_as_recipient && Some(addr) == sender {
+        return Err(ContractError::CustomError {
+            val: "the sender can't be a transfer recipient".into(),
+        });
+    }
+    // the query fails for anything that isn't a contract
+    if state.reject_contract_recipients && deps.querier.query_wasm_contract_info(addr).is_ok() {
+        return Err(ContractError::CustomError {
+            val: format!("{} is a contract and can't be a transfer recipient", addr),
+        });
+    }
+    if state.require_registered_recipients && !REGISTERED.has(deps.storage, addr.clone()) {
+        return Err(ContractError::CustomError {
+            val: format!("{} hasn't registered to receive transfers", addr),
+        });
+    }
+    Ok(())
+}
+
+/// multiplies the usei fee by `repeat_fee_multiplier` when `sender` transferred within the repeat
+/// window. returns whether it did. a fee paid in `fee_denom` is attached before this runs, so it is
+/// never escalated
+fn escalate_fee(
+    storage: &dyn Storage,
+    state: &State,
+    env: &Env,
+    sender: &Addr,
+    funds: Uint128,
+    fee_override: &mut Option<Uint128>,
+) -> Result<bool, ContractError> {
+    if state.repeat_window_secs == 0 || state.fee_denom.is_some() {
+        return Ok(false);
+    }
+    let repeat = LAST_TRANSFER
+        .may_load(storage, sender.clone())?
+        .is_some_and(|last| env.block.time < last.plus_seconds(state.repeat_window_secs));
+    if !repeat {
+        return Ok(false);
+    }
+    let fee = compute_fee(state, funds, *fee_override);
+    *fee_override = Some(fee.checked_mul(Uint128::from(state.repeat_fee_multiplier))?);
+    Ok(true)
+}
+
+/// in `fee_to_recipients` mode, zeroes the usei fee for `funds` and returns what it would have
+/// been, so it stays in the split
+fn waive_fee(state: &State, funds: Uint128, fee_override: &mut Option<Uint128>) -> Uint128 {
+    if !state.fee_to_recipients || state.fee_denom.is_some() {
+        return Uint128::zero();
+    }
+    let fee = compute_fee(state, funds, *fee_override);
+    *fee_override = Some(Uint128::zero());
+    fee
+}
+
+/// the fee and referral bonus for a deposit of `funds`. the fee is priced on the whole deposit,
+/// so it should be pinned before the bonus is taken out
+fn referral_bonus(
+    state: &State,
+    funds: Uint128,
+    fee_override: Option<Uint128>,
+) -> (Uint128, Uint128) {
+    let fee = compute_fee(state, funds, fee_override);
+    let to_send = if state.fee_denom.is_some() {
+        funds
+    } else {
+        funds.saturating_sub(fee)
+    };
+    (fee, to_send.multiply_ratio(state.referral_bps, 10000u32))
+}
+
+/// splits `funds` across `recipients` recipients, equally or by `weights`, and returns the fee,
+/// the equal share (the whole amount split when weighted), each recipient's share in order and
+/// the units left over for the sender. with `drop_small`, shares below `min_share` are zeroed and
+/// refunded rather than failing the split
+fn plan_split(
+    state: &State,
+    funds: Uint128,
+    fee_override: Option<Uint128>,
+    recipients: usize,
+    weights: Option<&[u128]>,
+    drop_small: bool,
+) -> Result<(Uint128, Uint128, Vec<Uint128>, Uint128), ContractError> {
+    let parts = if weights.is_some() { 1 } else { recipients };
+    let (fee, share) = split_funds(state, funds, fee_override, parts as u128)?;
+    let mut shares = match weights {
+        Some(weights) => weighted_shares(share, weights),
+        None => vec![share; recipients],
+    };
+    let too_small = |share: &Uint128| *share < state.min_share || share.is_zero();
+    // with `drop_small`, shares that are too small are zeroed instead, which leaves them out of
+    // the credits and puts them in the dust. at least one share has to remain
+    let small = if drop_small {
+        let largest = shares.iter().max().copied().unwrap_or_default();
+        shares
+            .iter_mut()
+            .filter(|s| too_small(s))
+            .for_each(|s| *s = Uint128::zero());
+        too_small(&largest).then_some(largest)
+    } else {
+        shares.iter().find(|s| too_small(s)).copied()
+    };
+    if let Some(small) = small {
+        return Err(ContractError::ShareTooSmall {
+            share: small,
+            min_share: state.min_share,
+        });
+    }
+    // units left over when the deposit isn't an exact multiple of the recipients after the fee
+    let paid_fee = if state.fee_denom.is_some() {
+        Uint128::zero()
+    } else {
+        fee
+    };
+    let dust = funds - paid_fee - shares.iter().sum::<Uint128>();
+    Ok((fee, share, shares, dust))
+}
+
+/// divides `amount` in proportion to `weights`, flooring each share, then hands the units lost to
+/// rounding out one at a time to the highest weights first. ties go to the earlier recipient, so
+/// the result only depends on the input. nothing is lost: the shares always sum to `amount`
+fn weighted_shares(amount: Uint128, weights: &[u128]) -> Vec<Uint128> {
+    let total: u128 = weights.iter().sum();
+    let mut shares: Vec<Uint128> = weights
+        .iter()
+        .map(|w| amount * Decimal::from_ratio(*w, total))
+        .collect();
+    let mut order: Vec<usize> = (0..weights.len()).collect();
+    order.sort_by(|a, b| weights[*b].cmp(&weights[*a]));
+    let mut leftover = (amount - shares.iter().sum::<Uint128>()).u128();
+    for i in order.iter().cycle() {
+        if leftover == 0 {
+            break;
+        }
+        shares[*i] += Uint128::one();
+        leftover -= 1;
+    }
+    shares
+}
+
+/// the account that is actually credited for `addr`
+fn forwarded(storage: &dyn Storage, addr: Addr) -> StdResult<Addr> {
+    Ok(FORWARDING_ADDRESSES
+        .may_load(storage, addr.clone())?
+        .unwrap_or(addr))
+}
+
+/// rejects callers that are neither the owner nor an admin
+fn ensure_admin(storage: &dyn Storage, state: &State, sender: &Addr) -> Result<(), ContractError> {
+    if *sender != state.owner && !ADMINS.has(storage, sender.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// rejects calls that attach any coins
+fn ensure_no_funds(info: &MessageInfo) -> Result<(), ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::CustomError {
+            val: "no funds required".into(),
+        });
+    }
+    Ok(())
+}
+
+/// requires exactly one coin of `denom` to be attached and returns its amount
+fn ensure_single_denom(info: &MessageInfo, denom: &str) -> Result<Uint128, ContractError> {
+    match info.funds.as_slice() {
+        [] => Err(ContractError::CustomError {
+            val: format!("please send {}", denom),
+        }),
+        [coin] if coin.denom == denom => Ok(coin.amount),
+        [coin] => Err(ContractError::CustomError {
+            val: format!(
+                "invalid denomination {}. please only send {}",
+                coin.denom, denom
+            ),
+        }),
+        _ => Err(ContractError::CustomError {
+            val: format!("please only send {}", denom),
+        }),
+    }
+}
+
+/// lowercases the attached denoms so `USEI` matches `usei`. ibc denoms are left alone, since their
+/// hash is case-sensitive
+fn lowercase_denoms(info: &MessageInfo) -> MessageInfo {
+    let mut info = info.clone();
+    for coin in &mut info.funds {
+        if !coin.denom.starts_with("ibc/") {
+            coin.denom = coin.denom.to_lowercase();
+        }
+    }
+    info
+}
+
+fn ensure_not_frozen(storage: &dyn Storage, account: &Addr) -> Result<(), ContractError> {
+    if FROZEN.has(storage, account.clone()) {
+        return Err(ContractError::CustomError {
+            val: "account is frozen".into(),
+        });
+    }
+    Ok(())
+}
+
+/// refuses to move funds out of an account the owner has put on hold
+fn ensure_not_held(storage: &dyn Storage, env: &Env, account: &Addr) -> Result<(), ContractError> {
+    if let Some(until) = HOLDS.may_load(storage, account.clone())? {
+        if env.block.time < until {
+            return Err(ContractError::CustomError {
+                val: format!("account is on hold until {}", until.seconds()),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// the cooldown, per-transaction cap and daily limit that apply to everything a holder debits
+fn check_withdraw_limits(
+    storage: &dyn Storage,
+    state: &State,
+    env: &Env,
+    account: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    // enforce the cooldown between withdrawals
+    if let Some(last) = LAST_WITHDRAW.may_load(storage, account.clone())? {
+        let next_allowed = last.plus_seconds(state.withdraw_cooldown_secs);
+        if env.block.time < next_allowed {
+            return Err(ContractError::CustomError {
+                val: format!(
+                    "withdraw cooldown active. next withdrawal allowed at {}",
+                    next_allowed
+                ),
+            });
+        }
+    }
+    if let Some(max) = state.max_withdraw_per_tx {
+        if amount > max {
+            return Err(ContractError::CustomError {
+                val: format!("can withdraw at most {} per transaction", max),
+            });
+        }
+    }
+    if let Some(limit) = WITHDRAW_LIMITS.may_load(storage, account.clone())? {
+        let withdrawn_today = withdrawn_today(storage, env, account)?;
+        if withdrawn_today + amount > limit {
+            return Err(ContractError::CustomError {
+                val: format!(
+                    "daily withdraw limit of {} reached. {} left today",
+                    limit,
+                    limit.saturating_sub(withdrawn_today)
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn withdrawn_today(storage: &dyn Storage, env: &Env, account: &Addr) -> StdResult<Uint128> {
+    let day = env.block.time.seconds() / SECONDS_PER_DAY;
+    Ok(DAILY_WITHDRAWN
+        .may_load(storage, (account.clone(), day))?
+        .unwrap_or_default())
+}
+
+/// starts the cooldown after a debit, counts it against the daily limit and keeps a remaining
+/// balance from looking inactive
+fn record_withdrawal(
+    storage: &mut dyn Storage,
+    env: &Env,
+    account: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    LAST_WITHDRAW.save(storage, account.clone(), &env.block.time)?;
+    if BALANCES.has(storage, account.clone()) {
+        LAST_ACTIVITY.save(storage, account.clone(), &env.block.time)?;
+    }
+    if WITHDRAW_LIMITS.has(storage, account.clone()) {
+        let day = env.block.time.seconds() / SECONDS_PER_DAY;
+        let total = withdrawn_today(storage, env, account)? + amount;
+        DAILY_WITHDRAWN.save(storage, (account.clone(), day), &total)?;
+    }
+    Ok(())
+}
+
+/// rejects addresses from other chains when a `required_prefix` is configured
+fn check_prefix(state: &State, addr: &Addr) -> Result<(), ContractError> {
+    match &state.required_prefix {
+        Some(prefix) if !addr.as_str().starts_with(prefix.as_str()) => {
+            Err(ContractError::CustomError {
+                val: format!("{} does not have the required prefix {}", addr, prefix),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// the fee that replaces the computed one, if any. a sender's override applies in either mode and
+/// wins over the usei entry in `FEES`, which only replaces the flat `send_fee`
+fn fee_override(
+    storage: &dyn Storage,
+    state: &State,
+    sender: Option<&Addr>,
+) -> StdResult<Option<Uint128>> {
+    if let Some(sender) = sender {
+        if let Some(fee) = SENDER_FEES.may_load(storage, sender.clone())? {
+            return Ok(Some(fee));
+        }
+    }
+    if state.fee_bps.is_some() {
+        return Ok(None);
+    }
+    FEES.may_load(storage, "usei".to_string())
+}
+
+/// overrides replace the charged fee as soon as they are saved, so they would let the owner skip
+/// the timelock on `send_fee`
+fn ensure_no_timelock(state: &State) -> Result<(), ContractError> {
+    if state.config_timelock_secs > 0 {
+        return Err(ContractError::CustomError {
+            val: "fee overrides can't be set while config changes are timelocked".into(),
+        });
+    }
+    Ok(())
+}
+
+/// the flat fee a transfer is charged, which is the usei entry in `FEES` when there is one
+fn charged_send_fee(storage: &dyn Storage, state: &State) -> StdResult<Uint128> {
+    Ok(fee_override(storage, state, None)?.unwrap_or(state.send_fee))
+}
+
+/// the fee charged for sending `funds`. `fee_override` replaces the fee when set. in percentage
+/// mode this is `fee_bps` of the funds, clamped between `min_fee` and `max_fee`. otherwise it is
+/// the flat `send_fee`. nothing during a fee holiday
+fn compute_fee(state: &State, funds: Uint128, fee_override: Option<Uint128>) -> Uint128 {
+    if state.fee_holiday {
+        return Uint128::zero();
+    }
+    if let Some(fee) = fee_override {
+        return fee;
+    }
+    match state.fee_bps {
+        Some(bps) => {
+            let mut fee = funds.multiply_ratio(bps, 10000u32);
+            if state.round_fee_up && !(funds.full_mul(bps) % Uint256::from(10000u32)).is_zero() {
+                fee += Uint128::one();
+            }
+            let fee = std::cmp::max(fee, state.min_fee);
+            match state.max_fee {
+                Some(max_fee) => std::cmp::min(fee, max_fee),
+                None => fee,
+            }
+        }
+        None => state.send_fee,
+    }
+}
+
+/// the smallest deposit that can be split between two recipients: the flat fee plus one usei for
+/// each. percentage fees and fees paid in `fee_denom` are checked by `split_funds` instead
+fn min_deposit(state: &State, mut fee_override: Option<Uint128>) -> Uint128 {
+    // a flat fee doesn't depend on the deposit, so it can be waived without one
+    waive_fee(state, Uint128::zero(), &mut fee_override);
+    let fee = if state.fee_bps.is_some() || state.fee_denom.is_some() {
+        Uint128::zero()
+    } else {
+        compute_fee(state, Uint128::zero(), fee_override)
+    };
+    fee + Uint128::from(2u32)
+}
+
+/// applies the fee to `funds` and returns the fee and the amount each of `parts` recipients is
+/// credited. in percentage fee mode the share is rounded down. a fee charged in a separate
+/// `fee_denom` is paid on top of the funds rather than out of them
+fn split_funds(
+    state: &State,
+    funds: Uint128,
+    fee_override: Option<Uint128>,
+    parts: u128,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let fee = compute_fee(state, funds, fee_override);
+
+    // ensure balance (minus the transfer fee) is even (instructions say to divide money evenly. requires an even number) and nonzero
+    // without a fee taken out of the deposit the only requirement is that usei was sent
+    let to_send = if state.fee_denom.is_some() || fee.is_zero() {
+        if funds == Uint128::from(0u32) {
+            return Err(ContractError::CustomError {
+                val: "please send usei".into(),
+            });
+        }
+        funds
+    } else {
+        if funds <= fee {
+            return Err(ContractError::CustomError {
+                val: "funds <= fee".into(),
+            });
+        }
+        // a fee paid in another denom can't be compared to the deposit
+        if let Some(max_fee_pct_bps) = state.max_fee_pct_bps {
+            if fee.multiply_ratio(10000u32, funds) > Uint128::from(max_fee_pct_bps) {
+                return Err(ContractError::FeeTooHighForAmount {
+                    fee,
+                    funds,
+                    max_fee_pct_bps,
+                });
+            }
+        }
+        funds - fee
+    };
+
+    // ensure the funds can be divided evenly
+    // to_send is guaranteed to be nonzero
+    let parts = Uint128::from(parts);
+    if to_send < parts {
+        return Err(ContractError::TooSmallToSplit { amount: to_send });
+    }
+    // a percentage fee, or an overpayment when refunds are on, can leave a few units over. those
+    // are refunded to the sender instead
+    let refunds_excess = state.fee_bps.is_some() || state.overpayment_refund;
+    if to_send % parts != Uint128::from(0u32) && !refunds_excess {
+        let fee_clause = if fee.is_zero() {
+            String::new()
+        } else {
+            format!(" + a fee of {}", fee)
+        };
+        let val = if parts == Uint128::from(2u32) {
+            format!(
+                "invalid funds. please send an even number of usei{}",
+                fee_clause
+            )
+        } else {
+            format!(
+                "invalid funds. please send a multiple of {} usei{}",
+                parts, fee_clause
+            )
+        };
+        return Err(ContractError::CustomError { val });
+    }
+
+    // calculate the amount to give to each account
+    // share is guaranteed to be nonzero
+    let share = to_send / parts;
+    if share < state.min_share {
+        return Err(ContractError::ShareTooSmall {
+            share,
+            min_share: state.min_share,
+        });
+    }
+    Ok((fee, share))
+}
+
+/// adds `amount` to the balance of `addr`, creating the account at the current height if needed,
+/// and returns the new balance. keeps `total_outstanding` in sync. the caller is responsible for
+/// saving `state`
+fn credit_balance(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    addr: &Addr,
+    amount: Uint128,
+    block: &BlockInfo,
+) -> Result<Uint128, ContractError> {
+    let height = block.height;
+    let balance = match BALANCES.may_load(storage, addr.clone())? {
+        Some(balance) => balance,
+        None => {
+            if let Some(max_accounts) = state.max_accounts {
+                if state.account_count >= max_accounts {
+                    return Err(ContractError::AccountLimitReached { max_accounts });
+                }
+            }
+            state.account_count += 1;
+            CREATED_HEIGHT.save(storage, addr.clone(), &height)?;
+            ACCOUNTS_BY_HEIGHT.save(storage, (height, addr.clone()), &())?;
+            Uint128::zero()
+        }
+    };
+    let new_balance = match Uint128::checked_add(balance, amount) {
+        Ok(r) => r,
+        Err(_) => {
+            return Err(ContractError::CustomError {
+                val: "balance overflow occured".into(),
+            })
+        }
+    };
+    BALANCES.save(storage, addr.clone(), &new_balance)?;
+    LAST_ACTIVITY.save(storage, addr.clone(), &block.time)?;
+    state.total_outstanding = state.total_outstanding.checked_add(amount)?;
+    Ok(new_balance)
+}
+
+/// removes `amount` from the balance of `addr` and returns the new balance. empty balances are
+/// deleted along with their metadata. keeps `total_outstanding` in sync. the caller is responsible
+/// for saving `state`
+fn debit_balance(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    addr: &Addr,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let balance = BALANCES
+        .may_load(storage, addr.clone())?
+        .unwrap_or_default();
+    if amount > balance {
+        return Err(ContractError::CustomError {
+            val: "insufficient funds".into(),
+        });
+    }
+
+    // deduct balance
+    let new_balance = balance - amount;
+
+    // delete empty balance
+    if new_balance == Uint128::from(0u32) {
+        BALANCES.remove(storage, addr.clone());
+        CREDITED_BY.remove(storage, addr.clone());
+        LAST_ACTIVITY.remove(storage, addr.clone());
+        if let Some(category) = BALANCE_CATEGORIES.may_load(storage, addr.clone())? {
+            BALANCE_CATEGORIES.remove(storage, addr.clone());
+            ACCOUNTS_BY_CATEGORY.remove(storage, (category, addr.clone()));
+        }
+        state.account_count = state.account_count.saturating_sub(1);
+        if let Some(height) = CREATED_HEIGHT.may_load(storage, addr.clone())? {
+            CREATED_HEIGHT.remove(storage, addr.clone());
+            ACCOUNTS_BY_HEIGHT.remove(storage, (height, addr.clone()));
+        }
+    } else {
+        BALANCES.save(storage, addr.clone(), &new_balance)?;
+    }
+    // the counter should never be smaller than a single balance. if it is, the two have drifted
+    // apart and that is a bug to investigate, but it must not block the user's debit
+    state.total_outstanding = state.total_outstanding.saturating_sub(amount);
+    Ok(new_balance)
+}
+
+pub fn execute_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.pause.withdrawals {
+        return Err(ContractError::CustomError {
+            val: "withdrawals are paused".into(),
+        });
+    }
+    if !state.refund_accidental_funds {
+        ensure_no_funds(&info)?;
+    }
+    // ensure account exists
+    if !BALANCES.has(deps.storage, info.sender.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    check_prefix(&state, &info.sender)?;
+    ensure_not_frozen(deps.storage, &info.sender)?;
+    ensure_not_held(deps.storage, &env, &info.sender)?;
+    check_withdraw_limits(deps.storage, &state, &env, &info.sender, amount)?;
+    // check balance
+    let balance = BALANCES.load(deps.storage, info.sender.clone())?;
+    if amount > balance {
+        return Err(ContractError::CustomError {
+            val: "insufficient funds".into(),
+        });
+    }
+    // a remainder below the threshold is swept to the owner so the account is closed
+    let remainder = balance - amount;
+    let dust = if remainder < state.dust_threshold {
+        remainder
+    } else {
+        Uint128::zero()
+    };
+
+    // fail clearly here instead of at the bank step if the coins were moved out of band
+    if state.verify_solvency_on_withdraw {
+        // attached coins are already in the contract's balance but are about to be refunded
+        let attached = info
+            .funds
+            .iter()
+            .filter(|coin| coin.denom == "usei")
+            .fold(Uint128::zero(), |total, coin| total + coin.amount);
+        let available = deps
+            .querier
+            .query_balance(&env.contract.address, "usei")?
+            .amount
+            .saturating_sub(attached);
+        if amount + dust > available {
+            if !state.auto_pause_on_insolvency {
+                return Err(ContractError::Insolvent {
+                    requested: amount + dust,
+                    available,
+                });
+            }
+            // an error would roll the pause back, so succeed without paying anything out
+            state.pause.transfers = true;
+            STATE.save(deps.storage, &state)?;
+            let mut res = Response::new()
+                .add_attribute("action", "withdraw")
+                .add_attribute("auto_paused", "true");
+            if !info.funds.is_empty() {
+                res = res.add_message(BankMsg::Send {
+                    to_address: info.sender.into(),
+                    amount: info.funds,
+                });
+            }
+            return Ok(res);
+        }
+    }
+
+    debit_balance(deps.storage, &mut state, &info.sender, amount + dust)?;
+    STATE.save(deps.storage, &state)?;
+    record_withdrawal(deps.storage, &env, &info.sender, amount)?;
+
+    // send coins, to the registered withdrawal address if there is one
+    let payout = WITHDRAWAL_ADDRESSES
+        .may_load(deps.storage, info.sender.clone())?
+        .unwrap_or_else(|| info.sender.clone());
+    let mut res = Response::new();
+    res = res.add_message(BankMsg::Send {
+        to_address: payout.to_string(),
+        amount: coins(amount.u128(), "usei"),
+    });
+    if !dust.is_zero() {
+        res = res
+            .add_attribute("dust_swept", dust)
+            .add_message(BankMsg::Send {
+                to_address: state.owner.to_string(),
+                amount: coins(dust.u128(), "usei"),
+            });
+    }
+
+    // give back anything that was attached by mistake
+    if !info.funds.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.into(),
+            amount: info.funds,
+        });
+    }
+
+    Ok(res.add_attribute("action", "withdraw"))
+}
+
+pub fn execute_withdraw_and_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    recipient_a: &str,
+    recipient_b: &str,
+) -> Result<Response, ContractError> {
+    debit_and_split(
+        deps,
+        env,
+        info,
+        amount,
+        recipient_a,
+        recipient_b,
+        "withdraw_and_transfer",
+    )
+}
+
+/// splits the caller's whole balance to two new recipients
+pub fn execute_forward_split(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient_a: &str,
+    recipient_b: &str,
+) -> Result<Response, ContractError> {
+    let balance = match BALANCES.may_load(deps.storage, info.sender.clone())? {
+        Some(balance) => balance,
+        None => return Err(ContractError::Unauthorized {}),
+    };
+    debit_and_split(
+        deps,
+        env,
+        info,
+        balance,
+        recipient_a,
+        recipient_b,
+        "forward_split",
+    )
+}
+
+/// debits `amount` from the caller and splits it as if it had been sent with `Transfer`
+fn debit_and_split(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    recipient_a: &str,
+    recipient_b: &str,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.pause.transfers || state.pause.withdrawals {
+        return Err(ContractError::CustomError {
+            val: "transfers or withdrawals are paused".into(),
+        });
+    }
+    ensure_no_funds(&info)?;
+    ensure_not_frozen(deps.storage, &info.sender)?;
+    // a fee in another denom would have to be attached, which isn't allowed here
+    if state.fee_denom.is_some() {
+        return Err(ContractError::CustomError {
+            val: "withdraw and transfer is not available while the fee is paid in another denom"
+                .into(),
+        });
+    }
+    // ensure account exists
+    if !BALANCES.has(deps.storage, info.sender.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    check_prefix(&state, &info.sender)?;
+    ensure_not_held(deps.storage, &env, &info.sender)?;
+    check_withdraw_limits(deps.storage, &state, &env, &info.sender, amount)?;
+
+    debit_balance(deps.storage, &mut state, &info.sender, amount)?;
+    record_withdrawal(deps.storage, &env, &info.sender, amount)?;
+    let (res, half) = split_and_credit(
+        deps,
+        &env,
+        &info.sender,
+        state,
+        amount,
+        &[recipient_a, recipient_b],
+        None,
+        None,
+        false,
+        action,
+    )?;
+    Ok(res
+        .add_attribute("recipient_a", half)
+        .add_attribute("recipient_b", half))
+}
+
+pub fn execute_withdraw_with_note(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    note: Option<String>,
+) -> Result<Response, ContractError> {
+    let note = match note {
+        Some(note) if note.len() > MAX_NOTE_LEN => {
+            return Err(ContractError::CustomError {
+                val: format!("note must be at most {} bytes", MAX_NOTE_LEN),
+            })
+        }
+        note => note,
+    };
+
+    let res = execute_withdraw(deps, env, info, amount)?;
+    // the note only goes on the response, never on the bank message
+    Ok(match note {
+        Some(note) => res.add_attribute("note", note),
+        None => res,
+    })
+}
+
+pub fn execute_withdraw_percent(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    bps: u16,
+) -> Result<Response, ContractError> {
+    if bps > 10000 {
+        return Err(ContractError::CustomError {
+            val: "bps must be at most 10000".into(),
+        });
+    }
+    // ensure account exists
+    let balance = match BALANCES.may_load(deps.storage, info.sender.clone())? {
+        Some(balance) => balance,
+        None => return Err(ContractError::Unauthorized {}),
+    };
+
+    // rounds down, so 10000 bps is exactly the whole balance and removes the account
+    let amount = balance.multiply_ratio(bps, 10000u32);
+    if amount == Uint128::from(0u32) {
+        return Err(ContractError::CustomError {
+            val: "withdrawal amount rounds to zero".into(),
+        });
+    }
+    execute_withdraw(deps, env, info, amount)
+}
+
+pub fn execute_withdraw_all(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // ensure account exists
+    let balance = match BALANCES.may_load(deps.storage, info.sender.clone())? {
+        Some(balance) => balance,
+        None => return Err(ContractError::Unauthorized {}),
+    };
+
+    // one attribute per coin paid out so indexers don't have to decode the bank messages
+    let withdrawn = coins(balance.u128(), "usei");
+    let mut res = execute_withdraw(deps, env, info, balance)?;
+    for coin in withdrawn {
+        res = res.add_attribute("withdrawn", coin.to_string());
+    }
+    Ok(res)
+}
+
+pub fn execute_set_withdrawal_address(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: &str,
+) -> Result<Response, ContractError> {
+    ensure_no_funds(&info)?;
+    let addr = deps.api.addr_validate(addr)?;
+    WITHDRAWAL_ADDRESSES.save(deps.storage, info.sender, &addr)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_withdrawal_address")
+        .add_attribute("addr", addr))
+}
+
+pub fn execute_register(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_no_funds(&info)?;
+    REGISTERED.save(deps.storage, info.sender.clone(), &())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register")
+        .add_attribute("account", info.sender))
+}
+
+pub fn execute_set_forwarding_address(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: &str,
+) -> Result<Response, ContractError> {
+    ensure_no_funds(&info)?;
+    let addr = deps.api.addr_validate(addr)?;
+    if addr == info.sender {
+        return Err(ContractError::CustomError {
+            val: "can't forward credits to yourself".into(),
+        });
+    }
+    FORWARDING_ADDRESSES.save(deps.storage, info.sender, &addr)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_forwarding_address")
+        .add_attribute("addr", addr))
+}
+
+pub fn execute_clear_forwarding(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_no_funds(&info)?;
+    FORWARDING_ADDRESSES.remove(deps.storage, info.sender);
+
+    Ok(Response::new().add_attribute("action", "clear_forwarding"))
+}
+
+pub fn execute_reject(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    // a reject pays out like a withdrawal, so it is paused along with them
+    if state.pause.withdrawals {
+        return Err(ContractError::CustomError {
+            val: "withdrawals are paused".into(),
+        });
+    }
+    ensure_no_funds(&info)?;
+    // ensure account exists
+    if !BALANCES.has(deps.storage, info.sender.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_not_frozen(deps.storage, &info.sender)?;
+    ensure_not_held(deps.storage, &env, &info.sender)?;
+    let sender = match CREDITED_BY.may_load(deps.storage, info.sender.clone())? {
+        Some(Some(sender)) => sender,
+        _ => {
+            return Err(ContractError::CustomError {
+                val: "the original sender can't be determined".into(),
+            })
+        }
+    };
+
+    // return the whole balance
+    let balance = BALANCES.load(deps.storage, info.sender.clone())?;
+    debit_balance(deps.storage, &mut state, &info.sender, balance)?;
+    STATE.save(deps.storage, &state)?;
+
+    let mut res = Response::new();
+    res = res
+        .add_message(BankMsg::Send {
+            to_address: sender.to_string(),
+            amount: coins(balance.u128(), "usei"),
+        })
+        .add_attribute("action", "reject")
+        .add_attribute("sender", sender);
+    Ok(res)
+}
+
+pub fn execute_seize_account(
+    deps: DepsMut,
+    info: MessageInfo,
+    account: &str,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    if reason.trim().is_empty() {
+        return Err(ContractError::CustomError {
+            val: "a reason is required".into(),
+        });
+    }
+
+    let addr = deps.api.addr_validate(account)?;
+    let balance = match BALANCES.may_load(deps.storage, addr.clone())? {
+        Some(balance) => balance,
+        None => {
+            return Err(ContractError::CustomError {
+                val: format!("{} has no balance", addr),
+            })
+        }
+    };
+    debit_balance(deps.storage, &mut state, &addr, balance)?;
+    STATE.save(deps.storage, &state)?;
+
+    let mut res = Response::new();
+    res = res
+        .add_message(BankMsg::Send {
+            to_address: state.owner.into(),
+            amount: coins(balance.u128(), "usei"),
+        })
+        .add_attribute("action", "seize")
+        .add_attribute("account", addr)
+        .add_attribute("reason", reason);
+    Ok(res)
+}
+
+pub fn execute_set_frozen(
+    deps: DepsMut,
+    info: MessageInfo,
+    accounts: Vec<String>,
+    frozen: bool,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    if accounts.len() > MAX_BATCH_ACCOUNTS {
+        return Err(ContractError::CustomError {
+            val: format!(
+                "at most {} accounts can be changed at once",
+                MAX_BATCH_ACCOUNTS
+            ),
+        });
+    }
+    // validate everything first so a bad entry doesn't leave the batch half applied
+    let accounts = accounts
+        .iter()
+        .map(|account| deps.api.addr_validate(account))
+        .collect::<StdResult<Vec<_>>>()?;
+    for account in &accounts {
+        if frozen {
+            FROZEN.save(deps.storage, account.clone(), &())?;
+        } else {
+            FROZEN.remove(deps.storage, account.clone());
+        }
+    }
+
+    let action = if frozen {
+        "freeze_many"
+    } else {
+        "unfreeze_many"
+    };
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("count", accounts.len().to_string()))
+}
+
+pub fn execute_set_withdraw_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    account: &str,
+    limit: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    let account = deps.api.addr_validate(account)?;
+    match limit {
+        Some(limit) => WITHDRAW_LIMITS.save(deps.storage, account.clone(), &limit)?,
+        None => WITHDRAW_LIMITS.remove(deps.storage, account.clone()),
+    }
+
+    let limit = limit.map_or_else(|| "none".to_string(), |limit| limit.to_string());
+    Ok(Response::new()
+        .add_attribute("action", "set_withdraw_limit")
+        .add_attribute("account", account)
+        .add_attribute("limit", limit))
+}
+
+pub fn execute_reclaim_inactive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    account: &str,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    let period = match state.inactivity_period_secs {
+        Some(period) => period,
+        None => {
+            return Err(ContractError::CustomError {
+                val: "no inactivity period is configured".into(),
+            })
+        }
+    };
+    let account = deps.api.addr_validate(account)?;
+    let balance = match BALANCES.may_load(deps.storage, account.clone())? {
+        Some(balance) => balance,
+        None => {
+            return Err(ContractError::CustomError {
+                val: format!("{} has no balance", account),
+            })
+        }
+    };
+    // balances from before activity was tracked count from instantiation
+    let last_activity = LAST_ACTIVITY
+        .may_load(deps.storage, account.clone())?
+        .unwrap_or(state.instantiated_at);
+    let inactive_for = env
+        .block
+        .time
+        .seconds()
+        .saturating_sub(last_activity.seconds());
+    if inactive_for <= period {
+        return Err(ContractError::CustomError {
+            val: format!(
+                "{} was active {} seconds ago, which is within the inactivity period of {}",
+                account, inactive_for, period
+            ),
+        });
+    }
+    debit_balance(deps.storage, &mut state, &account, balance)?;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: state.owner.to_string(),
+            amount: coins(balance.u128(), "usei"),
+        })
+        .add_attribute("action", "reclaim_inactive")
+        .add_attribute("account", account)
+        .add_attribute("amount", balance))
+}
+
+pub fn execute_hold_account(
+    deps: DepsMut,
+    info: MessageInfo,
+    account: &str,
+    until: Timestamp,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    let account = deps.api.addr_validate(account)?;
+    HOLDS.save(deps.storage, account.clone(), &until)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "hold_account")
+        .add_attribute("account", account)
+        .add_attribute("until", until.seconds().to_string()))
+}
+
+pub fn execute_reassign(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: &str,
+    to: &str,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+
+    let from = deps.api.addr_validate(from)?;
+    let to = deps.api.addr_validate(to)?;
+    if from == to {
+        return Err(ContractError::CustomError {
+            val: "cannot reassign an account to itself".into(),
+        });
+    }
+    let balance = match BALANCES.may_load(deps.storage, from.clone())? {
+        Some(balance) => balance,
+        None => {
+            return Err(ContractError::CustomError {
+                val: format!("{} has no balance", from),
+            })
+        }
+    };
+
+    // the original sender carries over, unless it conflicts with whoever credited `to`
+    let from_credited_by = CREDITED_BY.may_load(deps.storage, from.clone())?.flatten();
+    let credited_by = match CREDITED_BY.may_load(deps.storage, to.clone())? {
+        None => from_credited_by,
+        Some(to_credited_by) if to_credited_by == from_credited_by => to_credited_by,
+        Some(_) => None,
+    };
+
+    debit_balance(deps.storage, &mut state, &from, balance)?;
+    credit_balance(deps.storage, &mut state, &to, balance, &env.block)?;
+    CREDITED_BY.save(deps.storage, to.clone(), &credited_by)?;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "reassign")
+        .add_attribute("from", from)
+        .add_attribute("to", to)
+        .add_attribute("amount", balance))
+}
+
+pub fn execute_handover(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_owner: &str,
+    new_fee_recipient: &str,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    // validate both before changing either
+    let new_owner = deps
+        .api
+        .addr_validate(new_owner)
+        .map_err(|_| ContractError::CustomError {
+            val: format!("invalid owner address {}", new_owner),
+        })?;
+    let new_fee_recipient =
+        deps.api
+            .addr_validate(new_fee_recipient)
+            .map_err(|_| ContractError::CustomError {
+                val: format!("invalid fee recipient address {}", new_fee_recipient),
+            })?;
+    state.owner = new_owner;
+    state.fee_recipient = new_fee_recipient;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "handover")
+        .add_attribute("owner", state.owner)
+        .add_attribute("fee_recipient", state.fee_recipient))
+}
+
+pub fn execute_set_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: &str,
+    admin: bool,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    // only the owner manages admins, so an admin can't lock the others out
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    let addr = deps.api.addr_validate(addr)?;
+    let action = if admin {
+        ADMINS.save(deps.storage, addr.clone(), &())?;
+        "add_admin"
+    } else {
+        ADMINS.remove(deps.storage, addr.clone());
+        "remove_admin"
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("addr", addr))
+}
+
+pub fn execute_set_pause(
+    deps: DepsMut,
+    info: MessageInfo,
+    transfers: Option<bool>,
+    withdrawals: Option<bool>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    ensure_admin(deps.storage, &state, &info.sender)?;
+    ensure_no_funds(&info)?;
+    if let Some(transfers) = transfers {
+        state.pause.transfers = transfers;
+    }
+    if let Some(withdrawals) = withdrawals {
+        state.pause.withdrawals = withdrawals;
+    }
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_pause")
+        .add_attribute("transfers", state.pause.transfers.to_string())
+        .add_attribute("withdrawals", state.pause.withdrawals.to_string()))
+}
+
+pub fn execute_set_fee_holiday(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    ensure_admin(deps.storage, &state, &info.sender)?;
+    ensure_no_funds(&info)?;
+    state.fee_holiday = enabled;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_holiday")
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+pub fn execute_update_send_fee(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fee: Uint128,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    ensure_admin(deps.storage, &state, &info.sender)?;
+    ensure_no_funds(&info)?;
+    // the bounds are fixed at instantiation so users know how far the fee can move
+    if fee < state.min_fee_bound || fee > state.max_fee_bound {
+        return Err(ContractError::CustomError {
+            val: format!(
+                "fee must be between {} and {}",
+                state.min_fee_bound, state.max_fee_bound
+            ),
+        });
+    }
+    if state.config_timelock_secs == 0 {
+        state.send_fee = fee;
+        STATE.save(deps.storage, &state)?;
+
+        return Ok(Response::new()
+            .add_attribute("action", "update_send_fee")
+            .add_attribute("send_fee", fee));
+    }
+
+    // queue the update. a newer update replaces the queued one and restarts the timelock
+    let effective_at = env.block.time.plus_seconds(state.config_timelock_secs);
+    state.pending_fee = Some(PendingFee { fee, effective_at });
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_send_fee")
+        .add_attribute("pending_fee", fee)
+        .add_attribute("effective_at", effective_at.seconds().to_string()))
+}
+
+pub fn execute_apply_pending(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    let pending = match state.pending_fee.take() {
+        Some(pending) => pending,
+        None => {
+            return Err(ContractError::CustomError {
+                val: "no pending change".into(),
+            })
+        }
+    };
+    if env.block.time < pending.effective_at {
+        return Err(ContractError::CustomError {
+            val: format!(
+                "pending change is not effective until {}",
+                pending.effective_at.seconds()
+            ),
+        });
+    }
+    state.send_fee = pending.fee;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "apply_pending")
+        .add_attribute("send_fee", pending.fee))
+}
+
+/// sets the fee override for `sender`, or clears it when `fee` is `None`
+pub fn execute_set_sender_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    sender: &str,
+    fee: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_admin(deps.storage, &state, &info.sender)?;
+    ensure_no_funds(&info)?;
+    let sender = deps.api.addr_validate(sender)?;
+    let res = match fee {
+        Some(fee) => {
+            ensure_no_timelock(&state)?;
+            // overrides stay within the same bounds as `send_fee`
+            if fee < state.min_fee_bound || fee > state.max_fee_bound {
+                return Err(ContractError::CustomError {
+                    val: format!(
+                        "fee must be between {} and {}",
+                        state.min_fee_bound, state.max_fee_bound
+                    ),
+                });
+            }
+            SENDER_FEES.save(deps.storage, sender.clone(), &fee)?;
+            Response::new()
+                .add_attribute("action", "set_sender_fee")
+                .add_attribute("fee", fee)
+        }
+        None => {
+            SENDER_FEES.remove(deps.storage, sender.clone());
+            Response::new().add_attribute("action", "clear_sender_fee")
+        }
+    };
+    Ok(res.add_attribute("sender", sender))
+}
+
+pub fn execute_decommission(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    state.decommissioned = true;
+    // repeating the call doesn't move the recorded time
+    state.decommissioned_at.get_or_insert(env.block.time);
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new().add_attribute("action", "decommission"))
+}
+
+pub fn execute_refund_all(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: u32,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    // otherwise new balances could appear behind the cursor
+    if !state.decommissioned {
+        return Err(ContractError::CustomError {
+            val: "the contract must be decommissioned first".into(),
+        });
+    }
+
+    // collect first, since the entries are removed while paying out. frozen and held balances
+    // are left for the owner to resolve, as in `ClaimFor`
+    let mut balances = vec![];
+    for item in BALANCES.range(deps.storage, None, None, Order::Ascending) {
+        if balances.len() == limit as usize {
+            break;
+        }
+        let (addr, balance) = item?;
+        if ensure_not_frozen(deps.storage, &addr).is_ok()
+            && ensure_not_held(deps.storage, &env, &addr).is_ok()
+        {
+            balances.push((addr, balance));
+        }
+    }
+    let mut res = Response::new();
+    for (addr, balance) in &balances {
+        debit_balance(deps.storage, &mut state, addr, *balance)?;
+        let payout = WITHDRAWAL_ADDRESSES
+            .may_load(deps.storage, addr.clone())?
+            .unwrap_or_else(|| addr.clone());
+        res = res.add_message(BankMsg::Send {
+            to_address: payout.to_string(),
+            amount: coins(balance.u128(), "usei"),
+        });
+    }
+    // open escrows can't be released by the owner, so they go back to their senders once the
+    // balances are done
+    let escrows = ESCROWS
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit as usize - balances.len())
+        .collect::<StdResult<Vec<_>>>()?;
+    for (escrow_id, escrow) in &escrows {
+        ESCROWS.remove(deps.storage, *escrow_id);
+        state.total_escrowed = state.total_escrowed.saturating_sub(escrow.amount);
+        res = res.add_message(BankMsg::Send {
+            to_address: escrow.sender.to_string(),
+            amount: coins(escrow.amount.u128(), "usei"),
+        });
+    }
+    STATE.save(deps.storage, &state)?;
+
+    let refunded = balances.len() + escrows.len();
+    Ok(res
+        .add_attribute("action", "refund_all")
+        .add_attribute("refunded", refunded.to_string()))
+}
+
+pub fn execute_claim_for(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    accounts: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    if state.pause.withdrawals {
+        return Err(ContractError::CustomError {
+            val: "withdrawals are paused".into(),
+        });
+    }
+    if accounts.len() > MAX_BATCH_ACCOUNTS {
+        return Err(ContractError::CustomError {
+            val: format!(
+                "at most {} accounts can be claimed for at once",
+                MAX_BATCH_ACCOUNTS
+            ),
+        });
+    }
+
+    // what the contract can still pay out, when withdrawals are checked against it
+    let mut available = if state.verify_solvency_on_withdraw {
+        let balance = deps.querier.query_balance(&env.contract.address, "usei")?;
+        Some(balance.amount)
+    } else {
+        None
+    };
+    let mut res = Response::new();
+    let mut claimed = 0u32;
+    for account in &accounts {
+        let account = deps.api.addr_validate(account)?;
+        let balance = match BALANCES.may_load(deps.storage, account.clone())? {
+            Some(balance) => balance,
+            None => continue,
+        };
+        // accounts the holder couldn't withdraw from right now are left for a later claim
+        let blocked = ensure_not_frozen(deps.storage, &account).is_err()
+            || ensure_not_held(deps.storage, &env, &account).is_err()
+            || check_withdraw_limits(deps.storage, &state, &env, &account, balance).is_err()
+            || available.is_some_and(|available| balance > available);
+        if blocked {
+            continue;
+        }
+        if let Some(available) = available.as_mut() {
+            *available -= balance;
+        }
+        debit_balance(deps.storage, &mut state, &account, balance)?;
+        record_withdrawal(deps.storage, &env, &account, balance)?;
+        let payout = WITHDRAWAL_ADDRESSES
+            .may_load(deps.storage, account.clone())?
+            .unwrap_or(account);
+        res = res.add_message(BankMsg::Send {
+            to_address: payout.to_string(),
+            amount: coins(balance.u128(), "usei"),
+        });
+        claimed += 1;
+    }
+    STATE.save(deps.storage, &state)?;
+
+    Ok(res
+        .add_attribute("action", "claim_for")
+        .add_attribute("claimed", claimed.to_string()))
+}
+
+pub fn execute_set_group(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    members: Vec<String>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+
+    let mut validated: Vec<Addr> = Vec::with_capacity(members.len());
+    for member in &members {
+        let addr = deps.api.addr_validate(member)?;
+        if validated.contains(&addr) {
+            return Err(ContractError::CustomError {
+                val: format!("{} is listed more than once", addr),
+            });
+        }
+        validated.push(addr);
+    }
+    GROUPS.save(deps.storage, name.clone(), &validated)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_group")
+        .add_attribute("name", name)
+        .add_attribute("members", validated.len().to_string()))
+}
+
+pub fn execute_import_balances(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    entries: Vec<BalanceEntry>,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if state.decommissioned {
+        return Err(ContractError::CustomError {
+            val: "the contract is decommissioned".into(),
+        });
+    }
+    if entries.is_empty() {
+        return Err(ContractError::CustomError {
+            val: "nothing to import".into(),
+        });
+    }
+
+    let mut validated: Vec<(Addr, Uint128)> = Vec::with_capacity(entries.len());
+    let mut total = Uint128::zero();
+    for entry in &entries {
+        let addr = deps.api.addr_validate(&entry.account)?;
+        check_prefix(&state, &addr)?;
+        if entry.amount.is_zero() {
+            return Err(ContractError::CustomError {
+                val: format!("cannot import a zero balance for {}", addr),
+            });
+        }
+        if validated.iter().any(|(a, _)| *a == addr) {
+            return Err(ContractError::CustomError {
+                val: format!("{} is listed more than once", addr),
+            });
+        }
+        total = total.checked_add(entry.amount)?;
+        validated.push((addr, entry.amount));
+    }
+    // the imported balances must be backed by the deposit
+    let denom = denom.unwrap_or_else(|| "usei".to_string());
+    let funds = ensure_single_denom(&info, &denom)?;
+    if funds != total {
+        return Err(ContractError::CustomError {
+            val: format!("please send exactly {} {} to back the import", total, denom),
+        });
+    }
+
+    if denom != "usei" {
+        // balances only move in usei, so these wait for a migration to be consolidated
+        for (addr, amount) in &validated {
+            LEGACY_BALANCES.update(
+                deps.storage,
+                (addr.clone(), denom.clone()),
+                |balance| -> StdResult<_> { Ok(balance.unwrap_or_default().checked_add(*amount)?) },
+            )?;
+        }
+        LEGACY_TOTALS.update(deps.storage, denom.clone(), |legacy| -> StdResult<_> {
+            Ok(legacy.unwrap_or_default().checked_add(total)?)
+        })?;
+        return Ok(Response::new()
+            .add_attribute("action", "import_balances")
+            .add_attribute("denom", denom)
+            .add_attribute("imported", validated.len().to_string())
+            .add_attribute("total", total));
+    }
+
+    for (addr, amount) in &validated {
+        // imported funds have no sender to return to, so Reject mustn't send them to whoever
+        // credited the account before
+        if BALANCES.has(deps.storage, addr.clone()) {
+            CREDITED_BY.save(deps.storage, addr.clone(), &None)?;
+        }
+        credit_balance(deps.storage, &mut state, addr, *amount, &env.block)?;
+    }
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "import_balances")
+        .add_attribute("denom", denom)
+        .add_attribute("imported", validated.len().to_string())
+        .add_attribute("total", total))
+}
+
+pub fn execute_set_denom_migration(
+    deps: DepsMut,
+    info: MessageInfo,
+    from_denom: String,
+    to_denom: String,
+    rate: Uint128,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    if from_denom.is_empty() || from_denom == "usei" {
+        return Err(ContractError::CustomError {
+            val: "only a denom other than usei can be migrated".into(),
+        });
+    }
+    // usei is the only denom balances are kept in
+    if to_denom != "usei" {
+        return Err(ContractError::CustomError {
+            val: "balances can only be migrated to usei".into(),
+        });
+    }
+    if rate.is_zero() {
+        return Err(ContractError::CustomError {
+            val: "the migration rate must be positive".into(),
+        });
+    }
+    DENOM_MIGRATIONS.save(deps.storage, from_denom.clone(), &(to_denom.clone(), rate))?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_denom_migration")
+        .add_attribute("from_denom", from_denom)
+        .add_attribute("to_denom", to_denom)
+        .add_attribute("rate", rate))
+}
+
+pub fn execute_consolidate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.decommissioned {
+        return Err(ContractError::CustomError {
+            val: "the contract is decommissioned".into(),
+        });
+    }
+    ensure_no_funds(&info)?;
+
+    let legacy = LEGACY_BALANCES
+        .prefix(info.sender.clone())
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut migrated: Vec<(String, Uint128)> = vec![];
+    let mut credit = Uint128::zero();
+    for (denom, amount) in legacy {
+        if let Some((_, rate)) = DENOM_MIGRATIONS.may_load(deps.storage, denom.clone())? {
+            credit = credit.checked_add(amount.checked_mul(rate)?)?;
+            migrated.push((denom, amount));
+        }
+    }
+    if migrated.is_empty() {
+        return Err(ContractError::CustomError {
+            val: "nothing to consolidate. no migration is set for your legacy balances".into(),
+        });
+    }
+
+    // the legacy coins don't back usei, so the credit must come out of usei the contract holds
+    // beyond what it already owes
+    let contract_balance = deps
+        .querier
+        .query_balance(&env.contract.address, "usei")?
+        .amount;
+    let owed = state
+        .total_outstanding
+        .checked_add(state.fee_pool)?
+        .checked_add(state.total_escrowed)?
+        .checked_add(credit)?;
+    if contract_balance < owed {
+        return Err(ContractError::CustomError {
+            val: format!(
+                "the migration isn't funded. the contract needs {} more usei",
+                owed - contract_balance
+            ),
+        });
+    }
+
+    for (denom, amount) in &migrated {
+        LEGACY_BALANCES.remove(deps.storage, (info.sender.clone(), denom.clone()));
+        LEGACY_TOTALS.update(deps.storage, denom.clone(), |legacy| -> StdResult<_> {
+            Ok(legacy.unwrap_or_default().checked_sub(*amount)?)
+        })?;
+    }
+    // legacy funds have no sender to return to
+    if BALANCES.has(deps.storage, info.sender.clone()) {
+        CREDITED_BY.save(deps.storage, info.sender.clone(), &None)?;
+    }
+    credit_balance(deps.storage, &mut state, &info.sender, credit, &env.block)?;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "consolidate")
+        .add_attribute(
+            "denoms",
+            migrated
+                .iter()
+                .map(|(denom, _)| denom.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+        .add_attribute("credited", credit))
+}
+
+pub fn execute_set_denom_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    fee: Uint128,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    ensure_admin(deps.storage, &state, &info.sender)?;
+    ensure_no_funds(&info)?;
+    if denom.is_empty() {
+        return Err(ContractError::CustomError {
+            val: "denom must not be empty".into(),
+        });
+    }
+    // transfers only accept usei, so a fee for any other denom would never be charged
+    if denom != "usei" {
+        return Err(ContractError::CustomError {
+            val: format!("fees can only be set for usei, not {}", denom),
+        });
+    }
+    ensure_no_timelock(&state)?;
+    // denom fees stay within the same bounds as `send_fee`
+    if fee < state.min_fee_bound || fee > state.max_fee_bound {
+        return Err(ContractError::CustomError {
+            val: format!(
+                "fee must be between {} and {}",
+                state.min_fee_bound, state.max_fee_bound
+            ),
+        });
+    }
+    FEES.save(deps.storage, denom.clone(), &fee)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_denom_fee")
+        .add_attribute("denom", denom)
+        .add_attribute("fee", fee))
+}
+
+pub fn execute_set_max_accounts(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_accounts: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    // a cap below the current count only stops new accounts, existing ones keep their balance
+    state.max_accounts = max_accounts;
+    STATE.save(deps.storage, &state)?;
+
+    let max_accounts = max_accounts.map_or_else(|| "none".to_string(), |max| max.to_string());
+    Ok(Response::new()
+        .add_attribute("action", "set_max_accounts")
+        .add_attribute("max_accounts", max_accounts))
+}
+
+/// rebuilds `total_outstanding` and `account_count` from `BALANCES`. reads every account, so
+/// the gas cost grows with the number of balances and may exceed the block limit on a large map
+pub fn execute_reconcile_counters(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    let (total, count) = count_balances(deps.storage)?;
+    let res = Response::new()
+        .add_attribute("action", "reconcile_counters")
+        .add_attribute(
+            "total_outstanding_before",
+            state.total_outstanding.to_string(),
+        )
+        .add_attribute("total_outstanding_after", total.to_string())
+        .add_attribute("account_count_before", state.account_count.to_string())
+        .add_attribute("account_count_after", count.to_string());
+    state.total_outstanding = total;
+    state.account_count = count;
+    STATE.save(deps.storage, &state)?;
+    Ok(res)
+}
+
+pub fn execute_set_require_memo(
+    deps: DepsMut,
+    info: MessageInfo,
+    required: bool,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    state.require_memo = required;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_require_memo")
+        .add_attribute("required", required.to_string()))
+}
+
+/// the sum and number of entries in `BALANCES`, read one by one
+fn count_balances(storage: &dyn Storage) -> StdResult<(Uint128, u64)> {
+    let mut total = Uint128::zero();
+    let mut count = 0u64;
+    for item in BALANCES.range(storage, None, None, Order::Ascending) {
+        let (_, balance) = item?;
+        total = total.checked_add(balance)?;
+        count += 1;
+    }
+    Ok((total, count))
+}
+
+pub fn execute_set_attr_prefix(
+    deps: DepsMut,
+    info: MessageInfo,
+    prefix: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    if let Some(prefix) = &prefix {
+        if prefix.is_empty()
+            || !prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(ContractError::CustomError {
+                val: "attribute prefix must be letters, digits, `_` or `-`".into(),
+            });
+        }
+    }
+    state.attr_prefix = prefix.clone();
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_attr_prefix")
+        .add_attribute("prefix", prefix.unwrap_or_else(|| "none".into())))
+}
+
+pub fn execute_rescue_token(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    recipient: &str,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    // the usei balance backs user balances and the fee pool
+    if denom == "usei" {
+        return Err(ContractError::CustomError {
+            val: "usei can't be rescued".into(),
+        });
+    }
+    let recipient = deps.api.addr_validate(recipient)?;
+    let mut balance = deps
+        .querier
+        .query_balance(env.contract.address, denom.clone())?;
+    // coins backing unconsolidated legacy balances aren't stray
+    let legacy = LEGACY_TOTALS
+        .may_load(deps.storage, denom.clone())?
+        .unwrap_or_default();
+    balance.amount = balance.amount.saturating_sub(legacy);
+    if balance.amount.is_zero() {
+        return Err(ContractError::CustomError {
+            val: format!("no {} to rescue", denom),
+        });
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "rescue_token")
+        .add_attribute("denom", denom)
+        .add_attribute("amount", balance.amount)
+        .add_attribute("recipient", recipient.clone())
+        .add_message(BankMsg::Send {
+            to_address: recipient.into(),
+            amount: vec![balance],
+        }))
+}
+
+pub fn execute_snapshot_fees(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    // a second snapshot in the same block overwrites the first, which is the same value anyway
+    FEE_SNAPSHOTS.save(deps.storage, env.block.height, &state.total_fees_collected)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "snapshot_fees")
+        .add_attribute("height", env.block.height.to_string())
+        .add_attribute("total_fees_collected", state.total_fees_collected))
+}
+
+pub fn execute_fund_fee_pool(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let amount = ensure_single_denom(&info, "usei")?;
+    state.fee_pool = state.fee_pool.checked_add(amount)?;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_fee_pool")
+        .add_attribute("amount", amount)
+        .add_attribute("fee_pool", state.fee_pool))
+}
+
+pub fn execute_claim_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_no_funds(&info)?;
+    let amount = amount.unwrap_or(state.fee_pool);
+    if amount == Uint128::from(0u32) || amount > state.fee_pool {
+        return Err(ContractError::CustomError {
+            val: format!("can only claim between 1 and {} usei", state.fee_pool),
+        });
+    }
+    state.fee_pool -= amount;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: state.owner.into(),
+            amount: coins(amount.u128(), "usei"),
+        })
+        .add_attribute("action", "claim_fees")
+        .add_attribute("amount", amount))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetOwner {} => to_json_binary(&query_owner(deps)?),
+        QueryMsg::IsAdmin { addr } => to_json_binary(&query_is_admin(deps, &addr)?),
+        QueryMsg::IsRegistered { addr } => to_json_binary(&query_is_registered(deps, &addr)?),
+        QueryMsg::GetSendFee {} => to_json_binary(&query_send_fee(deps)?),
+        QueryMsg::GetConfig {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::ConfigValue { key } => to_json_binary(&query_config_value(deps, &key)?),
+        QueryMsg::GetBalance { account } => to_json_binary(&query_balance(deps, &account)?),
+        QueryMsg::ShareFor { amount } => to_json_binary(&query_share_for(deps, amount)?),
+        QueryMsg::IsEven { amount } => to_json_binary(&query_is_even(deps, amount)?),
+        QueryMsg::MaxRecipients {} => to_json_binary(&query_max_recipients(deps)?),
+        QueryMsg::SchemaVersion {} => to_json_binary(&SchemaVersionResponse {
+            msg_version: MSG_VERSION,
+        }),
+        QueryMsg::MinDeposit {} => to_json_binary(&query_min_deposit(deps)?),
+        QueryMsg::BalancesByCategory {
+            category,
+            start_after,
+            limit,
+        } => to_json_binary(&query_balances_by_category(
+            deps,
+            category,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::SmallestHolders { limit } => {
+            to_json_binary(&query_smallest_holders(deps, limit)?)
+        }
+        QueryMsg::AccountsCreatedBetween {
+            from_height,
+            to_height,
+        } => to_json_binary(&query_accounts_created_between(
+            deps,
+            from_height,
+            to_height,
+        )?),
+        QueryMsg::HoldUntil { account } => to_json_binary(&query_hold_until(deps, env, &account)?),
+        QueryMsg::WithdrawableAt { account } => {
+            to_json_binary(&query_withdrawable_at(deps, env, &account)?)
+        }
+        QueryMsg::ForwardingAddress { account } => {
+            to_json_binary(&query_forwarding_address(deps, &account)?)
+        }
+        QueryMsg::WithdrawalAddress { account } => {
+            to_json_binary(&query_withdrawal_address(deps, &account)?)
+        }
+        QueryMsg::NormalizeAddress { input } => {
+            to_json_binary(&query_normalize_address(deps, &input)?)
+        }
+        QueryMsg::IsKnownAccount { account } => {
+            to_json_binary(&query_is_known_account(deps, &account)?)
+        }
+        QueryMsg::LatestTransfer {} => to_json_binary(&query_latest_transfer(deps)?),
+        QueryMsg::TransfersBySender {
+            sender,
+            start_after,
+            limit,
+        } => to_json_binary(&query_transfers_by_sender(
+            deps,
+            &sender,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::TransfersToRecipient {
+            recipient,
+            start_after,
+            limit,
+        } => to_json_binary(&query_transfers_to_recipient(
+            deps,
+            &recipient,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::FeeSnapshots { start_after, limit } => {
+            to_json_binary(&query_fee_snapshots(deps, start_after, limit)?)
+        }
+        QueryMsg::FeePool {} => to_json_binary(&query_fee_pool(deps)?),
+        QueryMsg::CounterDrift {} => to_json_binary(&query_counter_drift(deps)?),
+        QueryMsg::OwnerEarnings {} => to_json_binary(&query_owner_earnings(deps)?),
+        QueryMsg::SenderFeesPaid { sender } => {
+            to_json_binary(&query_sender_fees_paid(deps, &sender)?)
+        }
+        QueryMsg::SenderTxCount { sender } => {
+            to_json_binary(&query_sender_tx_count(deps, &sender)?)
+        }
+        QueryMsg::DefaultPair { sender } => to_json_binary(&query_default_pair(deps, &sender)?),
+        QueryMsg::Group { name } => to_json_binary(&query_group(deps, name)?),
+        QueryMsg::SenderFee { sender } => to_json_binary(&query_sender_fee(deps, &sender)?),
+        QueryMsg::Accounting {} => to_json_binary(&query_accounting(deps, env)?),
+        QueryMsg::Overview {} => to_json_binary(&query_overview(deps, env)?),
+        QueryMsg::Stats {} => to_json_binary(&query_stats(deps)?),
+        QueryMsg::Flags {} => to_json_binary(&query_flags(deps)?),
+        QueryMsg::Lifecycle {} => to_json_binary(&query_lifecycle(deps)?),
+        QueryMsg::Status {} => to_json_binary(&query_status(deps)?),
+        #[cfg(feature = "debug-queries")]
+        QueryMsg::DumpState {} => to_json_binary(&STATE.load(deps.storage)?),
+        QueryMsg::ActiveDenoms {} => to_json_binary(&query_active_denoms(deps)?),
+        QueryMsg::SimulateTransferMsg { msg, amount } => {
+            to_json_binary(&query_simulate_transfer_msg(deps, *msg, amount)?)
+        }
+        QueryMsg::PreviewTransfer {
+            recipient_a,
+            recipient_b,
+            amount,
+        } => to_json_binary(&query_preview_transfer(
+            deps,
+            &recipient_a,
+            &recipient_b,
+            amount,
+        )?),
+    }
+}
+
+fn query_owner(deps: Deps) -> StdResult<GetOwnerResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(GetOwnerResponse { owner: state.owner })
+}
+
+fn query_is_admin(deps: Deps, addr: &str) -> StdResult<IsAdminResponse> {
+    let state = STATE.load(deps.storage)?;
+    let addr = deps.api.addr_validate(addr)?;
+    let admin = addr == state.owner || ADMINS.has(deps.storage, addr);
+    Ok(IsAdminResponse { admin })
+}
+
+fn query_is_registered(deps: Deps, addr: &str) -> StdResult<IsRegisteredResponse> {
+    let addr = deps.api.addr_validate(addr)?;
+    Ok(IsRegisteredResponse {
+        registered: REGISTERED.has(deps.storage, addr),
+    })
+}
+
+fn query_send_fee(deps: Deps) -> StdResult<GetSendFeeResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(GetSendFeeResponse {
+        fee: charged_send_fee(deps.storage, &state)?,
+    })
+}
+
+fn query_config(deps: Deps) -> StdResult<GetConfigResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(GetConfigResponse {
+        owner: state.owner,
+        fee_recipient: state.fee_recipient,
+        send_fee: state.send_fee,
+        fee_bps: state.fee_bps,
+        min_fee: state.min_fee,
+        max_fee: state.max_fee,
+        max_fee_pct_bps: state.max_fee_pct_bps,
+        referral_bps: state.referral_bps,
+        min_fee_bound: state.min_fee_bound,
+        max_fee_bound: state.max_fee_bound,
+        fee_denom: state.fee_denom,
+        denom_decimals: state.denom_decimals,
+        max_accounts: state.max_accounts,
+    })
+}
+
+fn query_config_value(deps: Deps, key: &str) -> StdResult<ConfigValueResponse> {
+    let state = STATE.load(deps.storage)?;
+    let value = match key {
+        "owner" => state.owner.to_string(),
+        "send_fee" => charged_send_fee(deps.storage, &state)?.to_string(),
+        "denom" => "usei".to_string(),
+        "paused" => (state.pause.transfers || state.pause.withdrawals).to_string(),
+        _ => return Err(StdError::generic_err(format!("unknown config key {}", key))),
+    };
+    Ok(ConfigValueResponse { value })
+}
+
+fn query_balance(deps: Deps, account: &str) -> StdResult<GetBalanceResponse> {
+    let addr = deps.api.addr_validate(account)?;
+
+    let balance = if BALANCES.has(deps.storage, addr.clone()) {
+        // returns error if key isn't present. have to check `has` first
+        BALANCES.load(deps.storage, addr)?
+    } else {
+        Uint128::from(0u32)
+    };
+    Ok(GetBalanceResponse { balance })
+}
+
+fn query_accounts_created_between(
+    deps: Deps,
+    from_height: u64,
+    to_height: u64,
+) -> StdResult<AccountsResponse> {
+    let accounts = ACCOUNTS_BY_HEIGHT
+        .prefix_range(
+            deps.storage,
+            Some(PrefixBound::inclusive(from_height)),
+            Some(PrefixBound::inclusive(to_height)),
+            Order::Ascending,
+        )
+        .take(MAX_LIMIT as usize)
+        .map(|item| item.map(|((_, account), _)| account))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AccountsResponse { accounts })
+}
+
+fn query_hold_until(deps: Deps, env: Env, account: &str) -> StdResult<HoldUntilResponse> {
+    let account = deps.api.addr_validate(account)?;
+    let until = HOLDS
+        .may_load(deps.storage, account)?
+        .filter(|until| env.block.time < *until);
+    Ok(HoldUntilResponse { until })
+}
+
+fn query_withdrawable_at(deps: Deps, env: Env, account: &str) -> StdResult<WithdrawableAtResponse> {
+    let state = STATE.load(deps.storage)?;
+    let account = deps.api.addr_validate(account)?;
+    let hold = HOLDS.may_load(deps.storage, account.clone())?;
+    let cooldown = LAST_WITHDRAW
+        .may_load(deps.storage, account.clone())?
+        .map(|last| last.plus_seconds(state.withdraw_cooldown_secs));
+    // a used up daily limit resets at the start of the next day
+    let exhausted = match WITHDRAW_LIMITS.may_load(deps.storage, account.clone())? {
+        Some(limit) => withdrawn_today(deps.storage, &env, &account)? >= limit,
+        None => false,
+    };
+    let next_day = exhausted.then(|| {
+        let day = env.block.time.seconds() / SECONDS_PER_DAY;
+        Timestamp::from_seconds((day + 1) * SECONDS_PER_DAY)
+    });
+    // the latest constraint wins, and one that has already passed doesn't apply
+    let at = hold
+        .max(cooldown)
+        .max(next_day)
+        .filter(|at| env.block.time < *at);
+    Ok(WithdrawableAtResponse { at })
+}
+
+fn query_forwarding_address(deps: Deps, account: &str) -> StdResult<ForwardingAddressResponse> {
+    let account = deps.api.addr_validate(account)?;
+    let addr = FORWARDING_ADDRESSES.may_load(deps.storage, account)?;
+    Ok(ForwardingAddressResponse { addr })
+}
+
+fn query_withdrawal_address(deps: Deps, account: &str) -> StdResult<WithdrawalAddressResponse> {
+    let account = deps.api.addr_validate(account)?;
+    let addr = WITHDRAWAL_ADDRESSES.may_load(deps.storage, account)?;
+    Ok(WithdrawalAddressResponse { addr })
+}
+
+fn query_normalize_address(deps: Deps, input: &str) -> StdResult<NormalizeAddressResponse> {
+    let addr = deps.api.addr_validate(input)?;
+    Ok(NormalizeAddressResponse { addr })
+}
+
+fn query_is_known_account(deps: Deps, account: &str) -> StdResult<IsKnownAccountResponse> {
+    let addr = deps.api.addr_validate(account)?;
+    let known = BALANCES.has(deps.storage, addr.clone()) || RECEIVED_TOTAL.has(deps.storage, addr);
+    Ok(IsKnownAccountResponse { known })
+}
+
+fn query_active_denoms(deps: Deps) -> StdResult<ActiveDenomsResponse> {
+    // balances are only ever held in usei and empty balances are deleted, so a single
+    // key is enough to know whether anything is outstanding. no need to range the whole map
+    let has_balances = BALANCES
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    let denoms = if has_balances {
+        vec!["usei".to_string()]
+    } else {
+        vec![]
+    };
+    Ok(ActiveDenomsResponse { denoms })
+}
+
+fn query_share_for(deps: Deps, amount: Uint128) -> StdResult<ShareForResponse> {
+    let state = STATE.load(deps.storage)?;
+    let mut fee_override = fee_override(deps.storage, &state, None)?;
+    waive_fee(&state, amount, &mut fee_override);
+    let (_, share) = split_funds(&state, amount, fee_override, 2)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    Ok(ShareForResponse { share })
+}
+
+fn query_is_even(deps: Deps, amount: Uint128) -> StdResult<IsEvenResponse> {
+    let state = STATE.load(deps.storage)?;
+    let mut fee_override = fee_override(deps.storage, &state, None)?;
+    waive_fee(&state, amount, &mut fee_override);
+    // a fee paid in `fee_denom` doesn't come out of the amount
+    let fee = if state.fee_denom.is_some() {
+        Uint128::zero()
+    } else {
+        compute_fee(&state, amount, fee_override)
+    };
+    let even = amount > fee && (amount - fee) % Uint128::from(2u32) == Uint128::zero();
+    Ok(IsEvenResponse { even })
+}
+
+fn query_max_recipients(deps: Deps) -> StdResult<MaxRecipientsResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(MaxRecipientsResponse {
+        max_recipients: state.max_recipients.unwrap_or(DEFAULT_MAX_RECIPIENTS),
+    })
+}
+
+fn query_min_deposit(deps: Deps) -> StdResult<MinDepositResponse> {
+    let state = STATE.load(deps.storage)?;
+    let fee_override = fee_override(deps.storage, &state, None)?;
+    Ok(MinDepositResponse {
+        min_deposit: min_deposit(&state, fee_override),
+    })
+}
+
+fn query_latest_transfer(deps: Deps) -> StdResult<LatestTransferResponse> {
+    // ids are sequential, so the last key is the most recent transfer
+    let transfer = HISTORY
+        .range(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map(|(_, record)| record);
+    Ok(LatestTransferResponse { transfer })
+}
+
+fn query_transfers_by_sender(
+    deps: Deps,
+    sender: &str,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<TransfersResponse> {
+    let sender = deps.api.addr_validate(sender)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let transfers = TRANSFERS_BY_SENDER
+        .prefix(sender)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|id| HISTORY.load(deps.storage, id?))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(TransfersResponse { transfers })
+}
+
+fn query_transfers_to_recipient(
+    deps: Deps,
+    recipient: &str,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<TransfersResponse> {
+    let recipient = deps.api.addr_validate(recipient)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let transfers = TRANSFERS_BY_RECIPIENT
+        .prefix(recipient)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|id| HISTORY.load(deps.storage, id?))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(TransfersResponse { transfers })
+}
+
+fn query_sender_fees_paid(deps: Deps, sender: &str) -> StdResult<SenderFeesPaidResponse> {
+    let sender = deps.api.addr_validate(sender)?;
+    let fees_paid = SENDER_FEES_PAID
+        .may_load(deps.storage, sender)?
+        .unwrap_or_default();
+    Ok(SenderFeesPaidResponse { fees_paid })
+}
+
+fn query_sender_tx_count(deps: Deps, sender: &str) -> StdResult<SenderTxCountResponse> {
+    let sender = deps.api.addr_validate(sender)?;
+    let count = SENDER_TX_COUNT
+        .may_load(deps.storage, sender)?
+        .unwrap_or_default();
+    Ok(SenderTxCountResponse { count })
+}
+
+fn query_default_pair(deps: Deps, sender: &str) -> StdResult<DefaultPairResponse> {
+    let sender = deps.api.addr_validate(sender)?;
+    let pair = DEFAULT_PAIRS.may_load(deps.storage, sender)?;
+    Ok(DefaultPairResponse { pair })
+}
+
+fn query_group(deps: Deps, name: String) -> StdResult<GroupResponse> {
+    let members = GROUPS.load(deps.storage, name)?;
+    Ok(GroupResponse { members })
+}
+
+fn query_sender_fee(deps: Deps, sender: &str) -> StdResult<SenderFeeResponse> {
+    let sender = deps.api.addr_validate(sender)?;
+    let fee = SENDER_FEES.may_load(deps.storage, sender)?;
+    Ok(SenderFeeResponse { fee })
+}
+
+fn query_fee_snapshots(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<FeeSnapshotsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let snapshots = FEE_SNAPSHOTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(height, total_fees_collected)| FeeSnapshot {
+                height,
+                total_fees_collected,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(FeeSnapshotsResponse { snapshots })
+}
+
+fn query_balances_by_category(
+    deps: Deps,
+    category: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AccountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .map(Bound::exclusive);
+    let accounts = ACCOUNTS_BY_CATEGORY
+        .prefix(category)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AccountsResponse { accounts })
+}
+
+fn query_smallest_holders(deps: Deps, limit: u32) -> StdResult<HoldersResponse> {
+    let limit = limit.min(MAX_HOLDERS_LIMIT) as usize;
+    // a max-heap of the smallest balances seen so far. the largest of them is evicted whenever
+    // a smaller one turns up, so memory stays bounded by `limit`
+    let mut heap = BinaryHeap::with_capacity(limit + 1);
+    for item in BALANCES.range(deps.storage, None, None, Order::Ascending) {
+        let (account, balance) = item?;
+        heap.push((balance, account));
+        if heap.len() > limit {
+            heap.pop();
+        }
+    }
+    let holders = heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|(balance, account)| Holder { account, balance })
+        .collect();
+    Ok(HoldersResponse { holders })
+}
+
+fn query_counter_drift(deps: Deps) -> StdResult<CounterDriftResponse> {
+    let state = STATE.load(deps.storage)?;
+    let (computed_total, computed_count) = count_balances(deps.storage)?;
+    Ok(CounterDriftResponse {
+        stored_total: state.total_outstanding,
+        computed_total,
+        stored_count: state.account_count,
+        computed_count,
+        drift: state.total_outstanding != computed_total || state.account_count != computed_count,
+    })
+}
+
+fn query_owner_earnings(deps: Deps) -> StdResult<OwnerEarningsResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(OwnerEarningsResponse {
+        claimable: state.fee_pool,
+        lifetime: state.total_fees_collected,
+    })
+}
+
+fn query_fee_pool(deps: Deps) -> StdResult<FeePoolResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(FeePoolResponse {
+        fee_pool: state.fee_pool,
+    })
+}
+
+fn query_accounting(deps: Deps, env: Env) -> StdResult<AccountingResponse> {
+    let state = STATE.load(deps.storage)?;
+    let contract_balance = deps
+        .querier
+        .query_balance(env.contract.address, "usei")?
+        .amount;
+    // anything above the liabilities is an out-of-band deposit; anything below is a bug or a sweep
+    let liabilities = state
+        .total_outstanding
+        .checked_add(state.fee_pool)?
+        .checked_add(state.total_escrowed)?;
+    Ok(AccountingResponse {
+        total_user_balances: state.total_outstanding,
+        fee_pool: state.fee_pool,
+        total_escrowed: state.total_escrowed,
+        contract_balance,
+        reconciled: contract_balance >= liabilities,
+    })
+}
+
+fn query_overview(deps: Deps, env: Env) -> StdResult<OverviewResponse> {
+    let config = query_config(deps)?;
+    let accounting = query_accounting(deps, env)?;
+    let pause = STATE.load(deps.storage)?.pause;
+    Ok(OverviewResponse {
+        config,
+        pause,
+        fee_pool: accounting.fee_pool,
+        total_outstanding: accounting.total_user_balances,
+        total_escrowed: accounting.total_escrowed,
+        contract_balance: accounting.contract_balance,
+        solvent: accounting.reconciled,
+    })
+}
+
+fn query_stats(deps: Deps) -> StdResult<StatsResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(StatsResponse {
+        transfer_count: state.transfer_count,
+        total_fees_collected: state.total_fees_collected,
+        total_outstanding: state.total_outstanding,
+        account_count: state.account_count,
+    })
+}
+
+fn query_flags(deps: Deps) -> StdResult<FlagsResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(FlagsResponse {
+        transfers_paused: state.pause.transfers,
+        withdrawals_paused: state.pause.withdrawals,
+        decommissioned: state.decommissioned,
+        fee_holiday: state.fee_holiday,
+        fee_to_recipients: state.fee_to_recipients,
+        strict_fee_recipient: state.strict_fee_recipient,
+        round_fee_up: state.round_fee_up,
+        reject_contract_recipients: state.reject_contract_recipients,
+        require_registered_recipients: state.require_registered_recipients,
+        allow_sender_as_recipient: state.allow_sender_as_recipient,
+        verify_solvency_on_withdraw: state.verify_solvency_on_withdraw,
+        auto_pause_on_insolvency: state.auto_pause_on_insolvency,
+        refund_accidental_funds: state.refund_accidental_funds,
+        overpayment_refund: state.overpayment_refund,
+        normalize_denom: state.normalize_denom,
+        auto_withdraw: state.auto_withdraw,
+        require_memo: state.require_memo,
+    })
+}
+
+fn query_lifecycle(deps: Deps) -> StdResult<LifecycleResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(LifecycleResponse {
+        instantiated_at: state.instantiated_at,
+        decommissioned_at: state.decommissioned_at,
+    })
+}
+
+fn query_status(deps: Deps) -> StdResult<StatusResponse> {
+    let state = STATE.load(deps.storage)?;
+    let status = if state.decommissioned {
+        Status::Decommissioned
+    } else if state.pause.transfers || state.pause.withdrawals {
+        Status::Paused
+    } else {
+        Status::Active
+    };
+    Ok(StatusResponse { status })
+}
+
+fn query_preview_transfer(
+    deps: Deps,
+    recipient_a: &str,
+    recipient_b: &str,
+    amount: Uint128,
+) -> StdResult<PreviewTransferResponse> {
+    let state = STATE.load(deps.storage)?;
+    let mut fee_override = fee_override(deps.storage, &state, None)?;
+    waive_fee(&state, amount, &mut fee_override);
+    let (_, half) = split_funds(&state, amount, fee_override, 2)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    // credits land on the forwarding address, if the recipient has one
+    let addr_a = forwarded(deps.storage, deps.api.addr_validate(recipient_a)?)?;
+    let addr_b = forwarded(deps.storage, deps.api.addr_validate(recipient_b)?)?;
+    let current_a = BALANCES
+        .may_load(deps.storage, addr_a.clone())?
+        .unwrap_or_default();
+    let current_b = BALANCES
+        .may_load(deps.storage, addr_b.clone())?
+        .unwrap_or_default();
+
+    // a single recipient listed twice receives both halves
+    let credit = if addr_a == addr_b { half + half } else { half };
+    Ok(PreviewTransferResponse {
+        recipient_a: BalancePreview {
+            current: current_a,
+            projected: current_a.checked_add(credit)?,
+        },
+        recipient_b: BalancePreview {
+            current: current_b,
+            projected: current_b.checked_add(credit)?,
+        },
+    })
+}
+
+fn query_simulate_transfer_msg(
+    deps: Deps,
+    msg: ExecuteMsg,
+    amount: Uint128,
+) -> StdResult<SimulateTransferMsgResponse> {
+    let state = STATE.load(deps.storage)?;
+    let (recipients, referrer, weights) = match msg {
+        ExecuteMsg::Transfer {
+            recipient_a,
+            recipient_b,
+            ..
+        }
+        | ExecuteMsg::TransferWithMemo {
+            recipient_a,
+            recipient_b,
+            ..
+        }
+        | ExecuteMsg::TransferWithEscrow {
+            recipient_a,
+            recipient_b,
+            ..
+        } => (vec![recipient_a, recipient_b], None, None),
+        ExecuteMsg::TransferWithReferral {
+            recipient_a,
+            recipient_b,
+            referrer,
+        } => (vec![recipient_a, recipient_b], Some(referrer), None),
+        ExecuteMsg::TransferToGroup { name } => {
+            let members = GROUPS
+                .may_load(deps.storage, name.clone())?
+                .filter(|members| !members.is_empty())
+                .ok_or_else(|| StdError::generic_err(format!("group {} has no members", name)))?;
+            (members.into_iter().map(String::from).collect(), None, None)
+        }
+        ExecuteMsg::TransferWeighted { recipients } => {
+            let weights = recipients
+                .iter()
+                .map(|r| r.weight.into())
+                .collect::<Vec<_>>();
+            (
+                recipients.into_iter().map(|r| r.addr).collect(),
+                None,
+                Some(weights),
+            )
+        }
+        ExecuteMsg::DistributePro { start_after, limit } => {
+            let (holders, _) = distribution_holders(deps, &state, None, start_after, limit)
+                .map_err(|e| StdError::generic_err(e.to_string()))?;
+            let (addrs, weights) = holders
+                .into_iter()
+                .map(|(addr, balance)| (addr.into_string(), balance.u128()))
+                .unzip();
+            (addrs, None, Some(weights))
+        }
+        _ => return Err(StdError::generic_err("not a transfer message")),
+    };
+
+    let mut fee_override = fee_override(deps.storage, &state, None)?;
+    waive_fee(&state, amount, &mut fee_override);
+    let mut referral = None;
+    if let Some(referrer) = referrer {
+        let (fee, bonus) = referral_bonus(&state, amount, fee_override);
+        fee_override = Some(fee);
+        if !bonus.is_zero() {
+            referral = Some((deps.api.addr_validate(&referrer)?, bonus));
+        }
+    }
+    let bonus = referral
+        .as_ref()
+        .map(|(_, bonus)| *bonus)
+        .unwrap_or_default();
+    let (fee, _, shares, refund) = plan_split(
+        &state,
+        amount - bonus,
+        fee_override,
+        recipients.len(),
+        weights.as_deref(),
+        true,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    let mut credits = vec![];
+    for (addr, amount) in recipients.iter().zip(shares) {
+        let addr = deps.api.addr_validate(addr)?;
+        credits.push((addr, amount));
+    }
+    credits.extend(referral);
+    let credits = credits
+        .into_iter()
+        .map(|(addr, amount)| {
+            Ok(SimulatedCredit {
+                addr: forwarded(deps.storage, addr)?,
+                amount,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(SimulateTransferMsgResponse {
+        credits,
+        fee,
+        refund,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info,
+    };
+    use cosmwasm_std::{
+        coin, coins, from_json, Addr, ContractInfoResponse, ContractResult, CosmosMsg, SystemError,
+        SystemResult, WasmQuery,
+    };
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+
+        // negative path - initializing the contract with money
         let info = mock_info("creator", &coins(1000, "usei"));
         let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone());
         assert!(res.is_err());
         match res.unwrap_err() {
-            ContractError::CustomError { val } => assert!(val.contains("shouldn't send")),
-            _ => assert!(false),
+            ContractError::CustomError { val } => assert!(val.contains("shouldn't send")),
+            _ => panic!(),
+        };
+
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // check owner
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+        let value: GetOwnerResponse = from_json(&res).unwrap();
+        assert_eq!("creator", value.owner);
+
+        // check send_fee
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetSendFee {}).unwrap();
+        let value: GetSendFeeResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(1u32), value.fee);
+
+        // check balance of nonexistent account
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBalance {
+                account: "random".into(),
+            },
+        )
+        .unwrap();
+        let value: GetBalanceResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(0u32), value.balance);
+    }
+
+    #[test]
+    fn send_coins_negative_path() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // negative path: send the wrong type of coin
+        let info = mock_info("sender_a", &coins(1, "BTC"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("invalid denomination")),
+            _ => panic!(),
+        };
+
+        // negative path: send multiple types of coin
+        let to_send = vec![coin(1, "usei"), coin(1, "usei")];
+        let info = mock_info("sender_a", &to_send);
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.as_str() == "please only send usei"),
+            _ => panic!(),
+        };
+
+        // negative path: send no coins
+        let info = mock_info("sender_a", &[]);
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.as_str() == "please send usei"),
+            _ => panic!(),
+        };
+
+        // negative path: send the wrong number of coins (odd number greater than fee)
+        // 4 - fee (1) = 3, which is not divisible by 2
+        let info = mock_info("sender_a", &coins(4, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("invalid funds")),
+            _ => panic!(),
+        };
+
+        // negative path: send the wrong number of coins (just send the fee)
+        let info = mock_info("sender_a", &coins(1, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::DepositTooSmall { funds, min_deposit } => {
+                assert_eq!(Uint128::from(1u32), funds);
+                assert_eq!(Uint128::from(3u32), min_deposit);
+            }
+            _ => panic!(),
+        };
+
+        // negative path: send the wrong number of coins (zero)
+        let info = mock_info("sender_a", &coins(0, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::DepositTooSmall { funds, min_deposit } => {
+                assert_eq!(Uint128::zero(), funds);
+                assert_eq!(Uint128::from(3u32), min_deposit);
+            }
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn send_coins() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // send coins to the same address
+        let info = mock_info("sender_a", &coins(3, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_a",
+        )
+        .unwrap();
+        // verify the creator was paid
+        assert!(res.messages.len() == 1);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+
+        // send coins to different addresses
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_b",
+            "recipient_c",
+        )
+        .unwrap();
+        // verify the creator was paid
+        assert!(res.messages.len() == 1);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+
+        // query balances of recipients
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBalance {
+                account: "recipient_a".into(),
+            },
+        )
+        .unwrap();
+        let value: GetBalanceResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(2u32), value.balance);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBalance {
+                account: "recipient_b".into(),
+            },
+        )
+        .unwrap();
+        let value: GetBalanceResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(3u32), value.balance);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBalance {
+                account: "recipient_c".into(),
+            },
+        )
+        .unwrap();
+        let value: GetBalanceResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(3u32), value.balance);
+    }
+
+    #[test]
+    fn withdraw_coins() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // send coins
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+
+        // query balance
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBalance {
+                account: "recipient_a".into(),
+            },
+        )
+        .unwrap();
+        let value: GetBalanceResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(3u32), value.balance);
+
+        // withdraw using account not listed
+        let info = mock_info("random", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32));
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // withdraw too many
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(4u32));
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("insufficient funds")),
+            _ => panic!(),
+        };
+
+        // send money with withdrawal request
+        let info = mock_info("recipient_a", &coins(1, "usei"));
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(4u32));
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("no funds required")),
+            _ => panic!(),
+        };
+
+        // withdraw less than total
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(2u32)).unwrap();
+
+        // verify the recipient was paid
+        assert!(res.messages.len() == 1);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient_a".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+
+        // query balance
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBalance {
+                account: "recipient_a".into(),
+            },
+        )
+        .unwrap();
+        let value: GetBalanceResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(1u32), value.balance);
+
+        // verify that recipient_a is within BALANCES, as this will be checked later
+        assert!(BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
+
+        // withdraw remaining
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32)).unwrap();
+
+        // verify the recipient was paid
+        assert!(res.messages.len() == 1);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient_a".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+
+        // query balance
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBalance {
+                account: "recipient_a".into(),
+            },
+        )
+        .unwrap();
+        let value: GetBalanceResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(0u32), value.balance);
+
+        // ensure BALANCES doesn't contain recipeint_a anymore
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
+    }
+
+    #[test]
+    fn withdraw_cooldown() {
+        // init the contract with a 60 second cooldown
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            withdraw_cooldown_secs: 60,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // send coins
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+
+        // first withdrawal is always allowed
+        let env = mock_env();
+        let info = mock_info("recipient_a", &[]);
+        execute_withdraw(deps.as_mut(), env.clone(), info, Uint128::from(1u32)).unwrap();
+
+        // second withdrawal during the cooldown is rejected
+        let mut env = env;
+        env.block.time = env.block.time.plus_seconds(59);
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw(deps.as_mut(), env.clone(), info, Uint128::from(1u32));
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("cooldown")),
+            _ => panic!(),
+        };
+
+        // the cooldown only applies to the account that withdrew
+        let info = mock_info("recipient_b", &[]);
+        execute_withdraw(deps.as_mut(), env.clone(), info, Uint128::from(1u32)).unwrap();
+
+        // withdrawal is allowed once the cooldown has passed
+        env.block.time = env.block.time.plus_seconds(1);
+        let info = mock_info("recipient_a", &[]);
+        execute_withdraw(deps.as_mut(), env, info, Uint128::from(1u32)).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetBalance {
+                account: "recipient_a".into(),
+            },
+        )
+        .unwrap();
+        let value: GetBalanceResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(1u32), value.balance);
+    }
+
+    #[test]
+    fn active_denoms() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // nothing is owed yet
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ActiveDenoms {}).unwrap();
+        let value: ActiveDenomsResponse = from_json(&res).unwrap();
+        assert!(value.denoms.is_empty());
+
+        // send coins
+        let info = mock_info("sender_a", &coins(3, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_a",
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ActiveDenoms {}).unwrap();
+        let value: ActiveDenomsResponse = from_json(&res).unwrap();
+        assert_eq!(vec!["usei".to_string()], value.denoms);
+
+        // withdraw everything
+        let info = mock_info("recipient_a", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(2u32)).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ActiveDenoms {}).unwrap();
+        let value: ActiveDenomsResponse = from_json(&res).unwrap();
+        assert!(value.denoms.is_empty());
+    }
+
+    #[test]
+    fn reject_coins() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // credit recipient_a twice from the same sender and recipient_b from two senders
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        let info = mock_info("sender_a", &coins(3, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_a",
+        )
+        .unwrap();
+        let info = mock_info("sender_b", &coins(3, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_b",
+            "recipient_b",
+        )
+        .unwrap();
+
+        // reject using account not listed
+        let info = mock_info("random", &[]);
+        let res = execute_reject(deps.as_mut(), mock_env(), info);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // merged credits can't be rejected
+        let info = mock_info("recipient_b", &[]);
+        let res = execute_reject(deps.as_mut(), mock_env(), info);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("can't be determined")),
+            _ => panic!(),
+        };
+
+        // not while withdrawals are paused
+        let mut state = STATE.load(&deps.storage).unwrap();
+        state.pause.withdrawals = true;
+        STATE.save(&mut deps.storage, &state).unwrap();
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_reject(deps.as_mut(), mock_env(), info);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!("withdrawals are paused", val),
+            _ => panic!(),
+        };
+        state.pause.withdrawals = false;
+        STATE.save(&mut deps.storage, &state).unwrap();
+
+        // nor from a frozen account
+        FROZEN
+            .save(&mut deps.storage, Addr::unchecked("recipient_a"), &())
+            .unwrap();
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_reject(deps.as_mut(), mock_env(), info);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!("account is frozen", val),
+            _ => panic!(),
+        };
+        FROZEN.remove(&mut deps.storage, Addr::unchecked("recipient_a"));
+
+        // reject the whole balance back to sender_a
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_reject(deps.as_mut(), mock_env(), info).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "sender_a".into(),
+                amount: coins(5, "usei"),
+            })
+        );
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
+        assert!(!CREDITED_BY.has(&deps.storage, Addr::unchecked("recipient_a")));
+    }
+
+    #[test]
+    fn withdraw_percent() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // send coins
+        let info = mock_info("sender_a", &coins(9, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+
+        // more than 100%
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw_percent(deps.as_mut(), mock_env(), info, 10001);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("at most 10000")),
+            _ => panic!(),
+        };
+
+        // withdraw half
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw_percent(deps.as_mut(), mock_env(), info, 5000).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient_a".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_a"))
+                .unwrap()
+        );
+
+        // withdraw everything
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw_percent(deps.as_mut(), mock_env(), info, 10000).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient_a".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
+    }
+
+    #[test]
+    fn withdraw_solvency_check() {
+        // init the contract with a querier that only knows about 1 usei
+        let mut deps = mock_dependencies_with_balance(&coins(1, "usei"));
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            verify_solvency_on_withdraw: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // send coins
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+
+        // the contract can't cover the withdrawal
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(2u32));
+        match res.unwrap_err() {
+            ContractError::Insolvent {
+                requested,
+                available,
+            } => {
+                assert_eq!(Uint128::from(2u32), requested);
+                assert_eq!(Uint128::from(1u32), available);
+            }
+            _ => panic!(),
+        };
+        // nothing was debited
+        assert_eq!(
+            Uint128::from(3u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_a"))
+                .unwrap()
+        );
+
+        // a withdrawal the contract can cover goes through
+        let info = mock_info("recipient_a", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32)).unwrap();
+    }
+
+    #[test]
+    fn preview_transfer() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // give recipient_a an existing balance
+        let info = mock_info("sender_a", &coins(3, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+
+        // different recipients each get half
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PreviewTransfer {
+                recipient_a: "recipient_a".into(),
+                recipient_b: "recipient_c".into(),
+                amount: Uint128::from(7u32),
+            },
+        )
+        .unwrap();
+        let value: PreviewTransferResponse = from_json(&res).unwrap();
+        assert_eq!(
+            BalancePreview {
+                current: Uint128::from(1u32),
+                projected: Uint128::from(4u32),
+            },
+            value.recipient_a
+        );
+        assert_eq!(
+            BalancePreview {
+                current: Uint128::from(0u32),
+                projected: Uint128::from(3u32),
+            },
+            value.recipient_b
+        );
+
+        // the same recipient twice gets both halves
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PreviewTransfer {
+                recipient_a: "recipient_a".into(),
+                recipient_b: "recipient_a".into(),
+                amount: Uint128::from(7u32),
+            },
+        )
+        .unwrap();
+        let value: PreviewTransferResponse = from_json(&res).unwrap();
+        let expected = BalancePreview {
+            current: Uint128::from(1u32),
+            projected: Uint128::from(7u32),
+        };
+        assert_eq!(expected, value.recipient_a);
+        assert_eq!(expected, value.recipient_b);
+
+        // the preview matches the actual transfer
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_a",
+        )
+        .unwrap();
+        assert_eq!(
+            Uint128::from(7u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_a"))
+                .unwrap()
+        );
+
+        // invalid amounts are rejected
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PreviewTransfer {
+                recipient_a: "recipient_a".into(),
+                recipient_b: "recipient_b".into(),
+                amount: Uint128::from(4u32),
+            },
+        );
+        assert!(res.unwrap_err().to_string().contains("invalid funds"));
+
+        // a forwarded recipient is previewed at its forwarding address
+        let msg = ExecuteMsg::SetForwardingAddress {
+            addr: "recipient_a".into(),
+        };
+        let info = mock_info("recipient_c", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PreviewTransfer {
+                recipient_a: "recipient_c".into(),
+                recipient_b: "recipient_a".into(),
+                amount: Uint128::from(7u32),
+            },
+        )
+        .unwrap();
+        let value: PreviewTransferResponse = from_json(&res).unwrap();
+        let expected = BalancePreview {
+            current: Uint128::from(7u32),
+            projected: Uint128::from(13u32),
+        };
+        assert_eq!(expected, value.recipient_a);
+        assert_eq!(expected, value.recipient_b);
+    }
+
+    #[test]
+    fn explicit_owner() {
+        // a valid explicit owner
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some("owner".into()),
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+        let value: GetOwnerResponse = from_json(&res).unwrap();
+        assert_eq!("owner", value.owner);
+
+        // the explicit owner is paid the fee
+        let info = mock_info("sender_a", &coins(3, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+
+        // an invalid explicit owner
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some("Not An Address".into()),
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("invalid owner address")),
+            _ => panic!(),
+        };
+
+        // no owner defaults to the sender
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: None,
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+        let value: GetOwnerResponse = from_json(&res).unwrap();
+        assert_eq!("creator", value.owner);
+    }
+
+    #[test]
+    fn percentage_fee_floor() {
+        // init the contract with a 1% fee and a floor of 2
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            fee_bps: Some(100),
+            min_fee: Uint128::from(2u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // small transfer: 1% of 10 rounds to 0, so the floor applies
+        let info = mock_info("sender_a", &coins(10, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+        assert_eq!(
+            Uint128::from(4u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_a"))
+                .unwrap()
+        );
+
+        // large transfer: 1% of 1000 is 10, above the floor
+        let info = mock_info("sender_a", &coins(1000, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_c",
+            "recipient_d",
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(10, "usei"),
+            })
+        );
+        assert_eq!(
+            Uint128::from(495u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_c"))
+                .unwrap()
+        );
+
+        // the floor still has to leave something to split
+        let info = mock_info("sender_a", &coins(2, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("funds <= fee")),
+            _ => panic!(),
+        };
+
+        // percentages above 100% are rejected
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            fee_bps: Some(10001),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("at most 10000")),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn seize_account() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // send coins
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+
+        // only the owner can seize
+        let info = mock_info("recipient_b", &[]);
+        let res = execute_seize_account(deps.as_mut(), info, "recipient_a", "fraud".into());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // a reason is required
+        let info = mock_info("creator", &[]);
+        let res = execute_seize_account(deps.as_mut(), info, "recipient_a", " ".into());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("reason is required")),
+            _ => panic!(),
+        };
+
+        // seize the balance
+        let info = mock_info("creator", &[]);
+        let res =
+            execute_seize_account(deps.as_mut(), info, "recipient_a", "fraud".into()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(3, "usei"),
+            })
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                ("action", "seize"),
+                ("account", "recipient_a"),
+                ("reason", "fraud")
+            ]
+        );
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
+
+        // the other recipient is untouched
+        assert_eq!(
+            Uint128::from(3u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_b"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn accounting() {
+        // init the contract with a querier that holds exactly what the users are owed
+        let mut deps = mock_dependencies_with_balance(&coins(6, "usei"));
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // send coins and withdraw some of them
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        let info = mock_info("sender_a", &coins(3, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_a",
+        )
+        .unwrap();
+        let info = mock_info("recipient_a", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(2u32)).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Accounting {}).unwrap();
+        let value: AccountingResponse = from_json(&res).unwrap();
+        assert_eq!(
+            AccountingResponse {
+                total_user_balances: Uint128::from(6u32),
+                fee_pool: Uint128::from(0u32),
+                total_escrowed: Uint128::zero(),
+                contract_balance: Uint128::from(6u32),
+                reconciled: true,
+            },
+            value
+        );
+
+        // the contract holds less than it owes
+        deps.querier
+            .update_balance(mock_env().contract.address, coins(5, "usei"));
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Accounting {}).unwrap();
+        let value: AccountingResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(5u32), value.contract_balance);
+        assert!(!value.reconciled);
+    }
+
+    #[test]
+    fn min_share() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            min_share: Uint128::from(3u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // below the minimum share
+        let info = mock_info("sender_a", &coins(5, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        match res.unwrap_err() {
+            ContractError::ShareTooSmall { share, min_share } => {
+                assert_eq!(Uint128::from(2u32), share);
+                assert_eq!(Uint128::from(3u32), min_share);
+            }
+            _ => panic!(),
+        };
+
+        // exactly the minimum share
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        assert_eq!(
+            Uint128::from(3u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_a"))
+                .unwrap()
+        );
+    }
+
+    #[cfg(feature = "debug-queries")]
+    #[test]
+    fn dump_state() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            fee_bps: Some(100),
+            min_fee: Uint128::from(2u32),
+            min_share: Uint128::from(3u32),
+            withdraw_cooldown_secs: 60,
+            verify_solvency_on_withdraw: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // send coins so the counters move
+        let info = mock_info("sender_a", &coins(10, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::DumpState {}).unwrap();
+        let value: State = from_json(&res).unwrap();
+        assert_eq!(STATE.load(&deps.storage).unwrap(), value);
+        assert_eq!(Addr::unchecked("creator"), value.owner);
+        assert_eq!(Some(100), value.fee_bps);
+        assert_eq!(60, value.withdraw_cooldown_secs);
+        assert_eq!(Uint128::from(8u32), value.total_outstanding);
+    }
+
+    #[test]
+    fn reassign() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // send coins
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+
+        // only the owner can reassign
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_reassign(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_c",
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // the source must have a balance
+        let info = mock_info("creator", &[]);
+        let res = execute_reassign(deps.as_mut(), mock_env(), info, "random", "recipient_c");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("has no balance")),
+            _ => panic!(),
+        };
+
+        // merge recipient_a into recipient_b
+        let info = mock_info("creator", &[]);
+        let res = execute_reassign(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                ("action", "reassign"),
+                ("from", "recipient_a"),
+                ("to", "recipient_b"),
+                ("amount", "3")
+            ]
+        );
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
+        assert_eq!(
+            Uint128::from(6u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_b"))
+                .unwrap()
+        );
+        assert_eq!(
+            Uint128::from(6u32),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+    }
+
+    #[test]
+    fn separate_fee_denom() {
+        // init the contract with the fee charged in uusdc
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(5u32),
+            fee_denom: Some("uusdc".into()),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the whole usei amount is split and the fee is paid in uusdc
+        let info = mock_info("sender_a", &[coin(4, "usei"), coin(5, "uusdc")]);
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(5, "uusdc"),
+            })
+        );
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_a"))
+                .unwrap()
+        );
+
+        // missing fee coin
+        let info = mock_info("sender_a", &coins(4, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert!(val.contains("please send a fee of 5uusdc"))
+            }
+            _ => panic!(),
+        };
+
+        // wrong fee amount
+        let info = mock_info("sender_a", &[coin(4, "usei"), coin(4, "uusdc")]);
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert!(val.contains("please send a fee of 5uusdc"))
+            }
+            _ => panic!(),
+        };
+
+        // fee coin in the wrong denom
+        let info = mock_info("sender_a", &[coin(4, "usei"), coin(5, "uatom")]);
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert!(val.contains("invalid denomination uatom"))
+            }
+            _ => panic!(),
+        };
+
+        // only the fee coin
+        let info = mock_info("sender_a", &coins(5, "uusdc"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.as_str() == "please send usei"),
+            _ => panic!(),
+        };
+
+        // fee_denom can't be usei
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(5u32),
+            fee_denom: Some("usei".into()),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn latest_transfer() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // no transfers yet
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::LatestTransfer {}).unwrap();
+        let value: LatestTransferResponse = from_json(&res).unwrap();
+        assert_eq!(None, value.transfer);
+
+        // send coins a few times
+        let info = mock_info("sender_a", &coins(3, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        let info = mock_info("sender_b", &coins(5, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_b",
+            "recipient_c",
+        )
+        .unwrap();
+        let mut env = mock_env();
+        env.block.height += 1;
+        let info = mock_info("sender_c", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            "recipient_c",
+            "recipient_a",
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::LatestTransfer {}).unwrap();
+        let value: LatestTransferResponse = from_json(&res).unwrap();
+        assert_eq!(
+            Some(TransferRecord {
+                id: 2,
+                sender: Addr::unchecked("sender_c"),
+                recipient_a: Addr::unchecked("recipient_c"),
+                recipient_b: Addr::unchecked("recipient_a"),
+                share: Uint128::from(3u32),
+                fee: Uint128::from(1u32),
+                block_height: env.block.height,
+            }),
+            value.transfer
+        );
+    }
+
+    #[test]
+    fn withdraw_with_accidental_funds() {
+        for refund_accidental_funds in [false, true] {
+            // init the contract
+            let mut deps = mock_dependencies();
+            let msg = InstantiateMsg {
+                send_fee: Uint128::from(1u32),
+                refund_accidental_funds,
+                ..Default::default()
+            };
+            let info = mock_info("creator", &[]);
+            instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+            // send coins
+            let info = mock_info("sender_a", &coins(7, "usei"));
+            execute_transfer(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                "recipient_a",
+                "recipient_b",
+            )
+            .unwrap();
+
+            // withdraw with stray coins attached
+            let stray = vec![coin(5, "uatom"), coin(1, "usei")];
+            let info = mock_info("recipient_a", &stray);
+            let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(2u32));
+            if !refund_accidental_funds {
+                match res.unwrap_err() {
+                    ContractError::CustomError { val } => {
+                        assert!(val.contains("no funds required"))
+                    }
+                    _ => panic!(),
+                };
+                continue;
+            }
+
+            // the withdrawal goes through and the stray coins are refunded
+            let res = res.unwrap();
+            assert_eq!(2, res.messages.len());
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "recipient_a".into(),
+                    amount: coins(2, "usei"),
+                })
+            );
+            assert_eq!(
+                res.messages[1].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "recipient_a".into(),
+                    amount: stray,
+                })
+            );
+            assert_eq!(
+                Uint128::from(1u32),
+                BALANCES
+                    .load(&deps.storage, Addr::unchecked("recipient_a"))
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn withdraw_all() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // withdraw using account not listed
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw_all(deps.as_mut(), mock_env(), info);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // send coins
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+
+        let info = mock_info("recipient_a", &[]);
+        let res = execute_withdraw_all(deps.as_mut(), mock_env(), info).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient_a".into(),
+                amount: coins(3, "usei"),
+            })
+        );
+        assert_eq!(
+            res.attributes,
+            vec![("action", "withdraw"), ("withdrawn", "3usei")]
+        );
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
+    }
+
+    #[test]
+    fn fee_holiday() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the owner can toggle the holiday
+        let info = mock_info("random", &[]);
+        let res = execute_set_fee_holiday(deps.as_mut(), info, true);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // during the holiday the whole deposit is split and no fee is sent
+        let info = mock_info("creator", &[]);
+        execute_set_fee_holiday(deps.as_mut(), info, true).unwrap();
+        let info = mock_info("sender_a", &coins(4, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("recipient_a"))
+                .unwrap()
+        );
+
+        // the fee returns once the holiday ends
+        let info = mock_info("creator", &[]);
+        execute_set_fee_holiday(deps.as_mut(), info, false).unwrap();
+        let info = mock_info("sender_a", &coins(5, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+    }
+
+    #[test]
+    fn required_prefix() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            required_prefix: Some("sei1".into()),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // a recipient from another chain
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "sei1alice", "cosmos1bob");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("required prefix sei1")),
+            _ => panic!(),
+        };
+
+        // matching recipients
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "sei1alice", "sei1bob").unwrap();
+        let info = mock_info("sei1alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(3u32)).unwrap();
+
+        // a balance held by an address without the prefix can't be paid out
+        BALANCES
+            .save(
+                &mut deps.storage,
+                Addr::unchecked("cosmos1bob"),
+                &Uint128::from(1u32),
+            )
+            .unwrap();
+        let info = mock_info("cosmos1bob", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32));
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("required prefix sei1")),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn update_send_fee_within_bounds() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(2u32),
+            min_fee_bound: Uint128::from(1u32),
+            max_fee_bound: Some(Uint128::from(5u32)),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the owner can update the fee
+        let info = mock_info("random", &[]);
+        let res = execute_update_send_fee(deps.as_mut(), mock_env(), info, Uint128::from(3u32));
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // in bounds
+        let info = mock_info("creator", &[]);
+        execute_update_send_fee(deps.as_mut(), mock_env(), info, Uint128::from(5u32)).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetSendFee {}).unwrap();
+        let value: GetSendFeeResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(5u32), value.fee);
+
+        // below the minimum
+        let info = mock_info("creator", &[]);
+        let res = execute_update_send_fee(deps.as_mut(), mock_env(), info, Uint128::from(0u32));
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("between 1 and 5")),
+            _ => panic!(),
+        };
+
+        // above the maximum
+        let info = mock_info("creator", &[]);
+        let res = execute_update_send_fee(deps.as_mut(), mock_env(), info, Uint128::from(6u32));
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("between 1 and 5")),
+            _ => panic!(),
+        };
+
+        // the rejected updates didn't change the fee
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetSendFee {}).unwrap();
+        let value: GetSendFeeResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(5u32), value.fee);
+
+        // the initial fee has to be within the bounds too
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(6u32),
+            max_fee_bound: Some(Uint128::from(5u32)),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn transfers_by_sender() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // interleave transfers from two senders
+        for (sender, amount) in [
+            ("sender_a", 3u128),
+            ("sender_b", 5),
+            ("sender_a", 7),
+            ("sender_a", 9),
+        ] {
+            let info = mock_info(sender, &coins(amount, "usei"));
+            execute_transfer(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                "recipient_a",
+                "recipient_b",
+            )
+            .unwrap();
+        }
+
+        let transfers_by = |sender: &str, start_after: Option<u64>, limit: Option<u32>| {
+            let res = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::TransfersBySender {
+                    sender: sender.into(),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap();
+            let value: TransfersResponse = from_json(&res).unwrap();
+            value
+                .transfers
+                .into_iter()
+                .map(|t| {
+                    assert_eq!(sender, t.sender);
+                    t.id
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // only the sender's own transfers are returned
+        assert_eq!(vec![0, 2, 3], transfers_by("sender_a", None, None));
+        assert_eq!(vec![1], transfers_by("sender_b", None, None));
+        assert!(transfers_by("sender_c", None, None).is_empty());
+
+        // paging
+        assert_eq!(vec![0, 2], transfers_by("sender_a", None, Some(2)));
+        assert_eq!(vec![3], transfers_by("sender_a", Some(2), Some(2)));
+    }
+
+    #[test]
+    fn auto_withdraw() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            auto_withdraw: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the recipients are paid directly alongside the fee
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        )
+        .unwrap();
+        let expected: Vec<CosmosMsg> = vec![
+            BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1, "usei"),
+            }
+            .into(),
+            BankMsg::Send {
+                to_address: "recipient_a".into(),
+                amount: coins(3, "usei"),
+            }
+            .into(),
+            BankMsg::Send {
+                to_address: "recipient_b".into(),
+                amount: coins(3, "usei"),
+            }
+            .into(),
+        ];
+        assert_eq!(
+            expected,
+            res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>()
+        );
+
+        // the same recipient twice gets one send
+        let info = mock_info("sender_a", &coins(7, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_a",
+        )
+        .unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient_a".into(),
+                amount: coins(6, "usei"),
+            })
+        );
+
+        // nothing is held in the contract
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("recipient_b")));
+        assert_eq!(
+            Uint128::from(0u32),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+    }
+
+    #[test]
+    fn strict_fee_recipient() {
+        for strict_fee_recipient in [false, true] {
+            // init the contract with a separate fee recipient
+            let mut deps = mock_dependencies();
+            let msg = InstantiateMsg {
+                send_fee: Uint128::from(1u32),
+                fee_recipient: Some("treasury".into()),
+                strict_fee_recipient,
+                ..Default::default()
+            };
+            let info = mock_info("creator", &[]);
+            instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+            // the fee goes to the fee recipient
+            let info = mock_info("sender_a", &coins(3, "usei"));
+            let res = execute_transfer(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                "recipient_a",
+                "recipient_b",
+            )
+            .unwrap();
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "treasury".into(),
+                    amount: coins(1, "usei"),
+                })
+            );
+
+            // name the fee recipient as a recipient
+            let info = mock_info("sender_a", &coins(3, "usei"));
+            let res = execute_transfer(deps.as_mut(), mock_env(), info, "recipient_a", "treasury");
+            if strict_fee_recipient {
+                match res.unwrap_err() {
+                    ContractError::CustomError { val } => {
+                        assert!(val.contains("fee recipient can't be a transfer recipient"))
+                    }
+                    _ => panic!(),
+                };
+            } else {
+                res.unwrap();
+                assert_eq!(
+                    Uint128::from(1u32),
+                    BALANCES
+                        .load(&deps.storage, Addr::unchecked("treasury"))
+                        .unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fund_fee_pool() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the owner can fund the pool
+        let info = mock_info("random", &coins(10, "usei"));
+        let res = execute_fund_fee_pool(deps.as_mut(), info);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // only usei is accepted
+        let info = mock_info("creator", &coins(10, "uatom"));
+        let res = execute_fund_fee_pool(deps.as_mut(), info);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("please only send usei")),
+            _ => panic!(),
+        };
+
+        // fund twice
+        let info = mock_info("creator", &coins(10, "usei"));
+        let res = execute_fund_fee_pool(deps.as_mut(), info).unwrap();
+        assert_eq!(res.attributes[0], ("action", "fund_fee_pool"));
+        let info = mock_info("creator", &coins(5, "usei"));
+        execute_fund_fee_pool(deps.as_mut(), info).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::FeePool {}).unwrap();
+        let value: FeePoolResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(15u32), value.fee_pool);
+        // user balances are untouched
+        assert_eq!(
+            Uint128::from(0u32),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+
+        // claim part of it back, then the rest
+        let info = mock_info("creator", &[]);
+        let res = execute_claim_fees(deps.as_mut(), info, Some(Uint128::from(16u32)));
+        assert!(res.is_err());
+        let info = mock_info("creator", &[]);
+        execute_claim_fees(deps.as_mut(), info, Some(Uint128::from(6u32))).unwrap();
+        let info = mock_info("creator", &[]);
+        let res = execute_claim_fees(deps.as_mut(), info, None).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(9, "usei"),
+            })
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::FeePool {}).unwrap();
+        let value: FeePoolResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(0u32), value.fee_pool);
+    }
+
+    #[test]
+    fn send_fee_plus_one() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(3u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // fee + 1 leaves a single unit, which is below the minimum deposit
+        let info = mock_info("sender_a", &coins(4, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        match res.unwrap_err() {
+            ContractError::DepositTooSmall { min_deposit, .. } => {
+                assert_eq!(Uint128::from(5u32), min_deposit)
+            }
+            _ => panic!(),
+        };
+        // the split itself still reports the leftover unit
+        let state = STATE.load(&deps.storage).unwrap();
+        match split_funds(&state, Uint128::from(4u32), None, 2).unwrap_err() {
+            ContractError::TooSmallToSplit { amount } => assert_eq!(Uint128::from(1u32), amount),
+            _ => panic!(),
+        };
+
+        // larger odd amounts still get the general message
+        let info = mock_info("sender_a", &coins(6, "usei"));
+        let res = execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "recipient_a",
+            "recipient_b",
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("invalid funds")),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn share_for() {
+        // init the contract
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // valid amount
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ShareFor {
+                amount: Uint128::from(7u32),
+            },
+        )
+        .unwrap();
+        let value: ShareForResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(3u32), value.share);
+
+        // invalid amounts
+        for (amount, message) in [(4u32, "invalid funds"), (1, "funds <= fee")] {
+            let res = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ShareFor {
+                    amount: Uint128::from(amount),
+                },
+            );
+            assert!(res.unwrap_err().to_string().contains(message));
+        }
+    }
+
+    #[test]
+    fn denom_decimals() {
+        // defaults to 6
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap();
+        let value: GetConfigResponse = from_json(&res).unwrap();
+        assert_eq!(6, value.denom_decimals);
+        assert_eq!(Uint128::from(1u32), value.send_fee);
+        assert_eq!("creator", value.fee_recipient);
+
+        // round trip an explicit value
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            denom_decimals: Some(18),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap();
+        let value: GetConfigResponse = from_json(&res).unwrap();
+        assert_eq!(18, value.denom_decimals);
+
+        // out of range
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            denom_decimals: Some(19),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("at most 18")),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn withdraw_note() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("sender", &coins(9, "usei"));
+        let msg = ExecuteMsg::Transfer {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            category: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // present note
+        let info = mock_info("alice", &[]);
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128::from(1u32),
+            note: Some("invoice-42".into()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "note" && attr.value == "invoice-42"));
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+
+        // absent note
+        let info = mock_info("bob", &[]);
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128::from(1u32),
+            note: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(!res.attributes.iter().any(|attr| attr.key == "note"));
+
+        // over-length note
+        let info = mock_info("bob", &[]);
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128::from(1u32),
+            note: Some("x".repeat(MAX_NOTE_LEN + 1)),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("at most 256 bytes")),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn set_pause() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(9, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        // only the owner can pause
+        let info = mock_info("random", &[]);
+        let res = execute_set_pause(deps.as_mut(), info, Some(true), None);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // transfers paused, withdrawals open
+        let info = mock_info("creator", &[]);
+        execute_set_pause(deps.as_mut(), info, Some(true), None).unwrap();
+        let info = mock_info("sender", &coins(9, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "transfers are paused"),
+            _ => panic!(),
+        };
+        let info = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32)).unwrap();
+
+        // both paused. leaving transfers as `None` keeps it paused
+        let info = mock_info("creator", &[]);
+        execute_set_pause(deps.as_mut(), info, None, Some(true)).unwrap();
+        assert_eq!(
+            PauseState {
+                transfers: true,
+                withdrawals: true,
+            },
+            STATE.load(&deps.storage).unwrap().pause
+        );
+        let info = mock_info("sender", &coins(9, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "transfers are paused"),
+            _ => panic!(),
+        };
+        let info = mock_info("bob", &[]);
+        let res = execute_withdraw_all(deps.as_mut(), mock_env(), info);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "withdrawals are paused"),
+            _ => panic!(),
+        };
+
+        // withdrawals paused, transfers open
+        let info = mock_info("creator", &[]);
+        execute_set_pause(deps.as_mut(), info, Some(false), None).unwrap();
+        let info = mock_info("sender", &coins(9, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let info = mock_info("bob", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32));
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "withdrawals are paused"),
+            _ => panic!(),
+        };
+
+        // neither paused
+        let info = mock_info("creator", &[]);
+        execute_set_pause(deps.as_mut(), info, None, Some(false)).unwrap();
+        let info = mock_info("sender", &coins(9, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let info = mock_info("bob", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32)).unwrap();
+    }
+
+    #[test]
+    fn is_known_account() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        let known = |deps: Deps, account: &str| -> bool {
+            let msg = QueryMsg::IsKnownAccount {
+                account: account.into(),
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: IsKnownAccountResponse = from_json(&res).unwrap();
+            value.known
+        };
+        assert!(known(deps.as_ref(), "alice"));
+        assert!(!known(deps.as_ref(), "random"));
+
+        // still known after withdrawing everything
+        let info = mock_info("alice", &[]);
+        execute_withdraw_all(deps.as_mut(), mock_env(), info).unwrap();
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("alice")));
+        assert!(known(deps.as_ref(), "alice"));
+    }
+
+    #[test]
+    fn max_fee_pct_bps() {
+        // the flat fee may be at most 10% of the deposit
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(2u32),
+            max_fee_pct_bps: Some(1000),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // exactly at the cap
+        let info = mock_info("sender", &coins(20, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(
+            Uint128::from(9u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+
+        // above the cap
+        let info = mock_info("sender", &coins(18, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob");
+        match res.unwrap_err() {
+            ContractError::FeeTooHighForAmount {
+                fee,
+                funds,
+                max_fee_pct_bps,
+            } => {
+                assert_eq!(Uint128::from(2u32), fee);
+                assert_eq!(Uint128::from(18u32), funds);
+                assert_eq!(1000, max_fee_pct_bps);
+            }
+            _ => panic!(),
+        };
+
+        // the cap itself must be a valid percentage
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            max_fee_pct_bps: Some(10001),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("max_fee_pct_bps")),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn config_value() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(3u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let config_value = |deps: Deps, key: &str| -> StdResult<String> {
+            let msg = QueryMsg::ConfigValue { key: key.into() };
+            let res = query(deps, mock_env(), msg)?;
+            let value: ConfigValueResponse = from_json(&res)?;
+            Ok(value.value)
+        };
+        assert_eq!("creator", config_value(deps.as_ref(), "owner").unwrap());
+        assert_eq!("3", config_value(deps.as_ref(), "send_fee").unwrap());
+        assert_eq!("usei", config_value(deps.as_ref(), "denom").unwrap());
+        assert_eq!("false", config_value(deps.as_ref(), "paused").unwrap());
+
+        let info = mock_info("creator", &[]);
+        execute_set_pause(deps.as_mut(), info, None, Some(true)).unwrap();
+        assert_eq!("true", config_value(deps.as_ref(), "paused").unwrap());
+
+        let err = config_value(deps.as_ref(), "fee_pool").unwrap_err();
+        assert!(err.to_string().contains("unknown config key fee_pool"));
+    }
+
+    #[test]
+    fn withdraw_and_transfer() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(2u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(14, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        // funds are not allowed
+        let info = mock_info("alice", &coins(1, "usei"));
+        let msg = ExecuteMsg::WithdrawAndTransfer {
+            amount: Uint128::from(6u32),
+            recipient_a: "carol".into(),
+            recipient_b: "dave".into(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "no funds required"),
+            _ => panic!(),
+        };
+
+        // drain alice's balance into a re-split
+        let info = mock_info("alice", &[]);
+        let msg = ExecuteMsg::WithdrawAndTransfer {
+            amount: Uint128::from(6u32),
+            recipient_a: "carol".into(),
+            recipient_b: "dave".into(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+        assert_eq!(1, res.messages.len());
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("alice")));
+        for account in ["carol", "dave"] {
+            assert_eq!(
+                Uint128::from(2u32),
+                BALANCES
+                    .load(&deps.storage, Addr::unchecked(account))
+                    .unwrap()
+            );
+        }
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(10u32), state.total_outstanding);
+        assert_eq!(2, state.transfer_count);
+        assert_eq!(
+            Addr::unchecked("alice"),
+            HISTORY.load(&deps.storage, 1).unwrap().sender
+        );
+
+        // can't re-split more than the balance
+        let info = mock_info("bob", &[]);
+        let msg = ExecuteMsg::WithdrawAndTransfer {
+            amount: Uint128::from(8u32),
+            recipient_a: "carol".into(),
+            recipient_b: "dave".into(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "insufficient funds"),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn config_timelock() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            config_timelock_secs: 100,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // nothing to apply yet
+        let res = execute_apply_pending(deps.as_mut(), mock_env());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "no pending change"),
+            _ => panic!(),
+        };
+
+        // the update is queued, not applied
+        let info = mock_info("creator", &[]);
+        execute_update_send_fee(deps.as_mut(), mock_env(), info, Uint128::from(5u32)).unwrap();
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(1u32), state.send_fee);
+        assert_eq!(
+            Some(PendingFee {
+                fee: Uint128::from(5u32),
+                effective_at: mock_env().block.time.plus_seconds(100),
+            }),
+            state.pending_fee
+        );
+
+        // too early
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(99);
+        let res = execute_apply_pending(deps.as_mut(), env.clone());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("not effective until")),
+            _ => panic!(),
+        };
+
+        // on time. anyone can apply it
+        env.block.time = env.block.time.plus_seconds(1);
+        let info = mock_info("random", &[]);
+        execute(deps.as_mut(), env, info, ExecuteMsg::ApplyPending {}).unwrap();
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(5u32), state.send_fee);
+        assert_eq!(None, state.pending_fee);
+
+        // overrides would take effect at once, so they can't be set at all
+        let msgs = [
+            ExecuteMsg::SetDenomFee {
+                denom: "usei".into(),
+                fee: Uint128::from(9u32),
+            },
+            ExecuteMsg::SetSenderFee {
+                sender: "sender".into(),
+                fee: Uint128::from(9u32),
+            },
+        ];
+        for msg in msgs {
+            let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg);
+            match res.unwrap_err() {
+                ContractError::CustomError { val } => assert!(val.contains("timelocked")),
+                _ => panic!(),
+            };
+        }
+        let info = mock_info("sender", &coins(7, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(5, "usei"),
+            })
+        );
+    }
+
+    #[test]
+    fn accounts_created_between() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (height, recipients) in [(100, ["alice", "bob"]), (200, ["carol", "alice"])] {
+            let mut env = mock_env();
+            env.block.height = height;
+            let info = mock_info("sender", &coins(5, "usei"));
+            execute_transfer(deps.as_mut(), env, info, recipients[0], recipients[1]).unwrap();
+        }
+        let mut env = mock_env();
+        env.block.height = 300;
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), env, info, "dave", "erin").unwrap();
+
+        let between = |deps: Deps, from_height: u64, to_height: u64| -> Vec<Addr> {
+            let msg = QueryMsg::AccountsCreatedBetween {
+                from_height,
+                to_height,
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: AccountsResponse = from_json(&res).unwrap();
+            value.accounts
+        };
+        // alice keeps the height her balance was created at
+        assert_eq!(
+            vec![
+                Addr::unchecked("alice"),
+                Addr::unchecked("bob"),
+                Addr::unchecked("carol")
+            ],
+            between(deps.as_ref(), 100, 200)
+        );
+        assert_eq!(
+            vec![Addr::unchecked("carol")],
+            between(deps.as_ref(), 101, 299)
+        );
+        assert!(between(deps.as_ref(), 301, 400).is_empty());
+
+        // an emptied balance leaves the index
+        let info = mock_info("bob", &[]);
+        execute_withdraw_all(deps.as_mut(), mock_env(), info).unwrap();
+        assert_eq!(
+            vec![Addr::unchecked("alice")],
+            between(deps.as_ref(), 100, 100)
+        );
+    }
+
+    #[test]
+    fn allow_sender_as_recipient() {
+        // allowed by default
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "sender").unwrap();
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("sender"))
+                .unwrap()
+        );
+
+        // rejected in either position when disabled
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            allow_sender_as_recipient: Some(false),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        for (recipient_a, recipient_b) in [("sender", "alice"), ("alice", "sender")] {
+            let info = mock_info("sender", &coins(5, "usei"));
+            let res = execute_transfer(deps.as_mut(), mock_env(), info, recipient_a, recipient_b);
+            match res.unwrap_err() {
+                ContractError::CustomError { val } => {
+                    assert_eq!(val, "the sender can't be a transfer recipient")
+                }
+                _ => panic!(),
+            };
+        }
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+    }
+
+    #[test]
+    fn overview() {
+        let mut deps = mock_dependencies_with_balance(&coins(7, "usei"));
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(9, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let info = mock_info("creator", &coins(3, "usei"));
+        execute_fund_fee_pool(deps.as_mut(), info).unwrap();
+        let info = mock_info("creator", &[]);
+        execute_set_pause(deps.as_mut(), info, Some(true), None).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Overview {}).unwrap();
+        let value: OverviewResponse = from_json(&res).unwrap();
+        assert_eq!(Addr::unchecked("creator"), value.config.owner);
+        assert_eq!(Uint128::from(1u32), value.config.send_fee);
+        assert_eq!(
+            PauseState {
+                transfers: true,
+                withdrawals: false,
+            },
+            value.pause
+        );
+        assert_eq!(Uint128::from(3u32), value.fee_pool);
+        assert_eq!(Uint128::from(8u32), value.total_outstanding);
+        assert_eq!(Uint128::zero(), value.total_escrowed);
+        // the mocked balance doesn't follow the bank messages, so 7 < 8 + 3
+        assert_eq!(Uint128::from(7u32), value.contract_balance);
+        assert!(!value.solvent);
+    }
+
+    #[test]
+    fn sender_fee() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(3u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the owner can set an override
+        let info = mock_info("partner", &[]);
+        let msg = ExecuteMsg::SetSenderFee {
+            sender: "partner".into(),
+            fee: Uint128::from(1u32),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetSenderFee {
+            sender: "partner".into(),
+            fee: Uint128::from(1u32),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let sender_fee = |deps: Deps, sender: &str| -> Option<Uint128> {
+            let msg = QueryMsg::SenderFee {
+                sender: sender.into(),
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: SenderFeeResponse = from_json(&res).unwrap();
+            value.fee
+        };
+        assert_eq!(
+            Some(Uint128::from(1u32)),
+            sender_fee(deps.as_ref(), "partner")
+        );
+        assert_eq!(None, sender_fee(deps.as_ref(), "sender"));
+
+        // the overridden sender pays 1, everyone else pays 3
+        let info = mock_info("partner", &coins(9, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+        let info = mock_info("sender", &coins(9, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(3, "usei"),
+            })
+        );
+        assert_eq!(
+            Uint128::from(7u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+
+        // cleared overrides fall back to the configured fee
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::ClearSenderFee {
+            sender: "partner".into(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(None, sender_fee(deps.as_ref(), "partner"));
+        let info = mock_info("partner", &coins(9, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(3, "usei"),
+            })
+        );
+    }
+
+    #[test]
+    fn round_fee_up() {
+        let setup = |round_fee_up: bool| {
+            let mut deps = mock_dependencies();
+            let msg = InstantiateMsg {
+                fee_bps: Some(100),
+                round_fee_up,
+                ..Default::default()
+            };
+            let info = mock_info("creator", &[]);
+            instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+            deps
+        };
+        let fee_sent = |res: Response| match &res.messages[0].msg {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => amount[0].amount,
+            _ => panic!(),
+        };
+
+        // 1% of 150 is 1.5: floored to 1 leaves an odd 149 and a unit of dust, ceiled to 2 leaves 148
+        let mut deps = setup(false);
+        let info = mock_info("sender", &coins(150, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "dust_refund" && a.value == "1"));
+        let mut deps = setup(true);
+        let info = mock_info("sender", &coins(150, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(Uint128::from(2u32), fee_sent(res));
+        assert_eq!(
+            Uint128::from(74u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+
+        // 1% of 250 is 2.5: floored to 2 leaves 248, ceiled to 3 leaves an odd 247
+        let mut deps = setup(false);
+        let info = mock_info("sender", &coins(250, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(Uint128::from(2u32), fee_sent(res));
+        let mut deps = setup(true);
+        let info = mock_info("sender", &coins(250, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(Uint128::from(3u32), fee_sent(res));
+
+        // exact percentages are not rounded
+        let mut deps = setup(true);
+        let info = mock_info("sender", &coins(200, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(Uint128::from(2u32), fee_sent(res));
+    }
+
+    #[test]
+    fn withdrawal_address() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // alice registers before she is credited
+        let info = mock_info("alice", &[]);
+        let msg = ExecuteMsg::SetWithdrawalAddress {
+            addr: "alice_cold".into(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let withdrawal_address = |deps: Deps, account: &str| -> Option<Addr> {
+            let msg = QueryMsg::WithdrawalAddress {
+                account: account.into(),
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: WithdrawalAddressResponse = from_json(&res).unwrap();
+            value.addr
+        };
+        assert_eq!(
+            Some(Addr::unchecked("alice_cold")),
+            withdrawal_address(deps.as_ref(), "alice")
+        );
+        assert_eq!(None, withdrawal_address(deps.as_ref(), "bob"));
+
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        // registered account is paid to its withdrawal address
+        let info = mock_info("alice", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(2u32)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice_cold".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+
+        // unregistered account is paid to itself
+        let info = mock_info("bob", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(2u32)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "bob".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+    }
+
+    #[test]
+    fn fee_snapshots() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the owner can snapshot
+        let info = mock_info("random", &[]);
+        let res = execute_snapshot_fees(deps.as_mut(), mock_env(), info);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        let mut env = mock_env();
+        for height in [100, 200] {
+            env.block.height = height;
+            for _ in 0..2 {
+                let info = mock_info("sender", &coins(5, "usei"));
+                execute_transfer(deps.as_mut(), env.clone(), info, "alice", "bob").unwrap();
+            }
+            let info = mock_info("creator", &[]);
+            execute_snapshot_fees(deps.as_mut(), env.clone(), info).unwrap();
+        }
+
+        let msg = QueryMsg::FeeSnapshots {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: FeeSnapshotsResponse = from_json(&res).unwrap();
+        assert_eq!(
+            vec![
+                FeeSnapshot {
+                    height: 100,
+                    total_fees_collected: Uint128::from(2u32),
+                },
+                FeeSnapshot {
+                    height: 200,
+                    total_fees_collected: Uint128::from(4u32),
+                },
+            ],
+            value.snapshots
+        );
+
+        // paginate
+        let msg = QueryMsg::FeeSnapshots {
+            start_after: Some(100),
+            limit: Some(1),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: FeeSnapshotsResponse = from_json(&res).unwrap();
+        assert_eq!(1, value.snapshots.len());
+        assert_eq!(200, value.snapshots[0].height);
+    }
+
+    #[test]
+    fn refund_all() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let info = mock_info("sender", &coins(7, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "carol", "bob").unwrap();
+
+        // not before decommissioning
+        let info = mock_info("creator", &[]);
+        let res = execute_refund_all(deps.as_mut(), mock_env(), info, 2);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("decommissioned first")),
+            _ => panic!(),
+        };
+
+        // only the owner can decommission, and no transfers are accepted afterwards
+        let info = mock_info("random", &[]);
+        let res = execute_decommission(deps.as_mut(), mock_env(), info);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+        let info = mock_info("creator", &[]);
+        execute_decommission(deps.as_mut(), mock_env(), info).unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "the contract is decommissioned"),
+            _ => panic!(),
+        };
+
+        // first batch
+        let info = mock_info("creator", &[]);
+        let res = execute_refund_all(deps.as_mut(), mock_env(), info, 2).unwrap();
+        assert_eq!(
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "alice".into(),
+                    amount: coins(2, "usei"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "bob".into(),
+                    amount: coins(5, "usei"),
+                }),
+            ],
+            res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>()
+        );
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "refunded" && attr.value == "2"));
+
+        // second batch empties the contract
+        let info = mock_info("creator", &[]);
+        let res = execute_refund_all(deps.as_mut(), mock_env(), info, 2).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            0,
+            BALANCES
+                .keys(&deps.storage, None, None, Order::Ascending)
+                .count()
+        );
+        assert_eq!(
+            Uint128::zero(),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+
+        // nothing left to refund
+        let info = mock_info("creator", &[]);
+        let res = execute_refund_all(deps.as_mut(), mock_env(), info, 2).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "refunded" && attr.value == "0"));
+    }
+
+    #[test]
+    fn refund_all_skips_blocked() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        let info = mock_info("sender", &coins(20, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "aaa", "bbb").unwrap();
+        let info = mock_info("sender", &coins(20, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "ccc", "ddd").unwrap();
+        let msg = ExecuteMsg::FreezeMany {
+            accounts: vec!["aaa".into()],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::HoldAccount {
+            account: "bbb".into(),
+            until: mock_env().block.time.plus_seconds(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetWithdrawalAddress {
+            addr: "ccc_wallet".into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("ccc", &[]), msg).unwrap();
+        let info = mock_info("creator", &[]);
+        execute_decommission(deps.as_mut(), mock_env(), info).unwrap();
+
+        // the frozen and held balances stay, and ccc is paid at its withdrawal address
+        let info = mock_info("creator", &[]);
+        let res = execute_refund_all(deps.as_mut(), mock_env(), info, 1).unwrap();
+        assert_eq!(
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                to_address: "ccc_wallet".into(),
+                amount: coins(10, "usei"),
+            })],
+            res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>()
+        );
+        let info = mock_info("creator", &[]);
+        execute_refund_all(deps.as_mut(), mock_env(), info, 10).unwrap();
+        let info = mock_info("creator", &[]);
+        let res = execute_refund_all(deps.as_mut(), mock_env(), info, 10).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "refunded" && attr.value == "0"));
+        for account in ["aaa", "bbb"] {
+            assert!(BALANCES.has(&deps.storage, Addr::unchecked(account)));
+        }
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("ddd")));
+    }
+
+    #[test]
+    fn refund_all_escrows() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::TransferWithEscrow {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            release_code_hash: Binary::from(Sha256::digest(b"secret").to_vec()),
+        };
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("creator", &[]);
+        execute_decommission(deps.as_mut(), mock_env(), info).unwrap();
+
+        // balances first, then the escrow goes back to its sender
+        let info = mock_info("creator", &[]);
+        let res = execute_refund_all(deps.as_mut(), mock_env(), info, 1).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+        let info = mock_info("creator", &[]);
+        let res = execute_refund_all(deps.as_mut(), mock_env(), info, 1).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "sender".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+        assert!(!ESCROWS.has(&deps.storage, 0));
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::zero(), state.total_escrowed);
+        assert_eq!(Uint128::zero(), state.total_outstanding);
+
+        let info = mock_info("creator", &[]);
+        let res = execute_refund_all(deps.as_mut(), mock_env(), info, 1).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "refunded" && attr.value == "0"));
+    }
+
+    #[test]
+    fn status() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let status = |deps: Deps| -> Status {
+            let res = query(deps, mock_env(), QueryMsg::Status {}).unwrap();
+            let value: StatusResponse = from_json(&res).unwrap();
+            value.status
+        };
+        assert_eq!(Status::Active, status(deps.as_ref()));
+
+        let info = mock_info("creator", &[]);
+        execute_set_pause(deps.as_mut(), info, None, Some(true)).unwrap();
+        assert_eq!(Status::Paused, status(deps.as_ref()));
+
+        let info = mock_info("creator", &[]);
+        execute_set_pause(deps.as_mut(), info, None, Some(false)).unwrap();
+        assert_eq!(Status::Active, status(deps.as_ref()));
+
+        // decommissioning wins over pausing
+        let info = mock_info("creator", &[]);
+        execute_set_pause(deps.as_mut(), info, Some(true), None).unwrap();
+        let info = mock_info("creator", &[]);
+        execute_decommission(deps.as_mut(), mock_env(), info).unwrap();
+        assert_eq!(Status::Decommissioned, status(deps.as_ref()));
+    }
+
+    #[test]
+    fn fee_rebate() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            rebate_every: 3,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let mut fee_recipients = vec![];
+        for _ in 0..3 {
+            let info = mock_info("sender", &coins(5, "usei"));
+            let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+            match &res.messages[0].msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    assert_eq!(coins(1, "usei"), *amount);
+                    fee_recipients.push(to_address.clone());
+                }
+                _ => panic!(),
+            }
+        }
+        // the third transfer's fee goes back to the sender
+        assert_eq!(vec!["creator", "creator", "sender"], fee_recipients);
+        assert_eq!(
+            Uint128::from(2u32),
+            STATE.load(&deps.storage).unwrap().total_fees_collected
+        );
+
+        // counts are per sender
+        let info = mock_info("other", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let tx_count = |deps: Deps, sender: &str| -> u64 {
+            let msg = QueryMsg::SenderTxCount {
+                sender: sender.into(),
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: SenderTxCountResponse = from_json(&res).unwrap();
+            value.count
+        };
+        assert_eq!(3, tx_count(deps.as_ref(), "sender"));
+        assert_eq!(1, tx_count(deps.as_ref(), "other"));
+        assert_eq!(0, tx_count(deps.as_ref(), "random"));
+    }
+
+    #[test]
+    fn ensure_no_funds_helper() {
+        ensure_no_funds(&mock_info("sender", &[])).unwrap();
+        let res = ensure_no_funds(&mock_info("sender", &coins(1, "usei")));
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "no funds required"),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn ensure_single_denom_helper() {
+        let amount = ensure_single_denom(&mock_info("sender", &coins(7, "usei")), "usei").unwrap();
+        assert_eq!(Uint128::from(7u32), amount);
+
+        let cases = [
+            (vec![], "please send usei"),
+            (
+                coins(7, "uatom"),
+                "invalid denomination uatom. please only send usei",
+            ),
+            (
+                vec![coin(7, "usei"), coin(7, "uatom")],
+                "please only send usei",
+            ),
+        ];
+        for (funds, expected) in cases {
+            let res = ensure_single_denom(&mock_info("sender", &funds), "usei");
+            match res.unwrap_err() {
+                ContractError::CustomError { val } => assert_eq!(val, expected),
+                _ => panic!(),
+            };
+        }
+    }
+
+    #[test]
+    fn transfer_to_group() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the owner can define groups
+        let msg = ExecuteMsg::SetGroup {
+            name: "team".into(),
+            members: vec!["alice".into(), "bob".into(), "carol".into()],
+        };
+        let info = mock_info("random", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+        let info = mock_info("creator", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Group {
+                name: "team".into(),
+            },
+        )
+        .unwrap();
+        let value: GroupResponse = from_json(&res).unwrap();
+        assert_eq!(3, value.members.len());
+
+        // 10 - 1 fee = 9 split three ways
+        let info = mock_info("sender", &coins(10, "usei"));
+        let msg = ExecuteMsg::TransferToGroup {
+            name: "team".into(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        for member in ["alice", "bob", "carol"] {
+            assert_eq!(
+                Uint128::from(3u32),
+                BALANCES
+                    .load(&deps.storage, Addr::unchecked(member))
+                    .unwrap()
+            );
+        }
+        assert_eq!(
+            Uint128::from(9u32),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+
+        // not divisible by three
+        let info = mock_info("sender", &coins(9, "usei"));
+        let msg = ExecuteMsg::TransferToGroup {
+            name: "team".into(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("a multiple of 3")),
+            _ => panic!(),
+        };
+
+        // unknown and empty groups
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetGroup {
+            name: "empty".into(),
+            members: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        for (name, expected) in [
+            ("nobody", "unknown group nobody"),
+            ("empty", "group empty has no members"),
+        ] {
+            let info = mock_info("sender", &coins(10, "usei"));
+            let msg = ExecuteMsg::TransferToGroup { name: name.into() };
+            let res = execute(deps.as_mut(), mock_env(), info, msg);
+            match res.unwrap_err() {
+                ContractError::CustomError { val } => assert_eq!(val, expected),
+                _ => panic!(),
+            };
+        }
+    }
+
+    #[test]
+    fn denom_fee() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let fee_sent = |res: Response| match &res.messages[0].msg {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => amount[0].amount,
+            _ => panic!(),
+        };
+
+        // only the owner can set denom fees
+        let msg = ExecuteMsg::SetDenomFee {
+            denom: "uatom".into(),
+            fee: Uint128::from(5u32),
+        };
+        let info = mock_info("random", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // other denoms are never transferred, so their fees are rejected
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("fees can only be set for usei, not uatom", val)
+            }
+            _ => panic!(),
+        };
+
+        // usei is unlisted, so the flat send_fee applies
+        let info = mock_info("sender", &coins(9, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(Uint128::from(1u32), fee_sent(res));
+
+        // once listed, the usei fee replaces send_fee
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetDenomFee {
+            denom: "usei".into(),
+            fee: Uint128::from(3u32),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(9, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(Uint128::from(3u32), fee_sent(res));
+        // and is what the fee queries report
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetSendFee {}).unwrap();
+        let value: GetSendFeeResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(3u32), value.fee);
+        let msg = QueryMsg::ConfigValue {
+            key: "send_fee".into(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: ConfigValueResponse = from_json(&res).unwrap();
+        assert_eq!("3", value.value);
+        assert_eq!(
+            Uint128::from(7u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn normalize_address() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = QueryMsg::NormalizeAddress {
+            input: "recipient_a".into(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: NormalizeAddressResponse = from_json(&res).unwrap();
+        assert_eq!(Addr::unchecked("recipient_a"), value.addr);
+
+        // the mock api rejects addresses that aren't normalized
+        let msg = QueryMsg::NormalizeAddress {
+            input: "Recipient_A".into(),
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    }
+
+    #[test]
+    fn max_withdraw_per_tx() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            max_withdraw_per_tx: Some(Uint128::from(3u32)),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(15, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        // above the cap
+        let info = mock_info("alice", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(4u32));
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!(val, "can withdraw at most 3 per transaction")
+            }
+            _ => panic!(),
+        };
+        let info = mock_info("alice", &[]);
+        execute_withdraw_all(deps.as_mut(), mock_env(), info).unwrap_err();
+
+        // at the cap
+        let info = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(3u32)).unwrap();
+        assert_eq!(
+            Uint128::from(4u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn credit_type_attributes() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let credit_types = |res: &Response| -> Vec<String> {
+            res.attributes
+                .iter()
+                .filter(|attr| attr.key == "credit_type")
+                .map(|attr| attr.value.clone())
+                .collect()
+        };
+
+        // first-time credits
+        let info = mock_info("sender", &coins(5, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(vec!["new", "new"], credit_types(&res));
+
+        // alice is topped up, carol is new
+        let info = mock_info("sender", &coins(5, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "carol").unwrap();
+        assert_eq!(vec!["topup", "new"], credit_types(&res));
+    }
+
+    #[test]
+    fn reject_contract_recipients() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { contract_addr } if contract_addr == "some_contract" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&ContractInfoResponse::default()).unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::NoSuchContract {
+                addr: "".to_string(),
+            }),
+        });
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            reject_contract_recipients: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("sender", &coins(5, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "some_contract");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!(
+                    val,
+                    "some_contract is a contract and can't be a transfer recipient"
+                )
+            }
+            _ => panic!(),
+        };
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        // allowed by default
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|_| {
+            SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&ContractInfoResponse::default()).unwrap(),
+            ))
+        });
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "some_contract").unwrap();
+    }
+
+    #[test]
+    fn sender_fees_paid() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            fee_bps: Some(1000),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // 10% of 20, 40 and 60
+        for amount in [20, 40, 60] {
+            let info = mock_info("sender", &coins(amount, "usei"));
+            execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        }
+        let info = mock_info("other", &coins(20, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        let fees_paid = |deps: Deps, sender: &str| -> Uint128 {
+            let msg = QueryMsg::SenderFeesPaid {
+                sender: sender.into(),
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: SenderFeesPaidResponse = from_json(&res).unwrap();
+            value.fees_paid
+        };
+        assert_eq!(Uint128::from(12u32), fees_paid(deps.as_ref(), "sender"));
+        assert_eq!(Uint128::from(2u32), fees_paid(deps.as_ref(), "other"));
+        assert_eq!(Uint128::zero(), fees_paid(deps.as_ref(), "random"));
+    }
+
+    #[test]
+    fn default_pair() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let default_pair = |deps: Deps, sender: &str| -> Option<(Addr, Addr)> {
+            let msg = QueryMsg::DefaultPair {
+                sender: sender.into(),
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: DefaultPairResponse = from_json(&res).unwrap();
+            value.pair
+        };
+
+        // unset
+        assert_eq!(None, default_pair(deps.as_ref(), "sender"));
+        let info = mock_info("sender", &coins(5, "usei"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::TransferToMyDefault {},
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(val, "no default pair set"),
+            _ => panic!(),
+        };
+
+        // set
+        let info = mock_info("sender", &[]);
+        let msg = ExecuteMsg::SetDefaultPair {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            Some((Addr::unchecked("alice"), Addr::unchecked("bob"))),
+            default_pair(deps.as_ref(), "sender")
+        );
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::TransferToMyDefault {},
+        )
+        .unwrap();
+        for account in ["alice", "bob"] {
+            assert_eq!(
+                Uint128::from(2u32),
+                BALANCES
+                    .load(&deps.storage, Addr::unchecked(account))
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn recipient_balance_attributes() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let attr = |res: &Response, key: &str| -> String {
+            res.attributes
+                .iter()
+                .find(|attr| attr.key == key)
+                .unwrap()
+                .value
+                .clone()
+        };
+        let balance = |deps: Deps, account: &str| -> String {
+            let msg = QueryMsg::GetBalance {
+                account: account.into(),
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: GetBalanceResponse = from_json(&res).unwrap();
+            value.balance.to_string()
+        };
+
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let info = mock_info("sender", &coins(7, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "carol").unwrap();
+        assert_eq!("5", attr(&res, "recipient_a_balance"));
+        assert_eq!(
+            balance(deps.as_ref(), "alice"),
+            attr(&res, "recipient_a_balance")
+        );
+        assert_eq!(
+            balance(deps.as_ref(), "carol"),
+            attr(&res, "recipient_b_balance")
+        );
+
+        // the same recipient twice reports the merged balance
+        let info = mock_info("sender", &coins(5, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "bob", "bob").unwrap();
+        assert_eq!("6", attr(&res, "recipient_a_balance"));
+        assert_eq!("6", attr(&res, "recipient_b_balance"));
+        assert_eq!(
+            balance(deps.as_ref(), "bob"),
+            attr(&res, "recipient_a_balance")
+        );
+    }
+
+    #[test]
+    fn hold_account() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        let until = mock_env().block.time.plus_seconds(100);
+        let msg = ExecuteMsg::HoldAccount {
+            account: "alice".into(),
+            until,
+        };
+        let info = mock_info("random", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+        let info = mock_info("creator", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let hold_until = |deps: Deps, env: Env| -> Option<Timestamp> {
+            let msg = QueryMsg::HoldUntil {
+                account: "alice".into(),
+            };
+            let res = query(deps, env, msg).unwrap();
+            let value: HoldUntilResponse = from_json(&res).unwrap();
+            value.until
+        };
+        assert_eq!(Some(until), hold_until(deps.as_ref(), mock_env()));
+
+        // held, on every path that moves funds out
+        let held_msgs = [
+            ExecuteMsg::Withdraw {
+                amount: Uint128::from(1u32),
+                note: None,
+            },
+            ExecuteMsg::WithdrawAndTransfer {
+                amount: Uint128::from(2u32),
+                recipient_a: "carol".into(),
+                recipient_b: "dave".into(),
+            },
+            ExecuteMsg::ForwardSplit {
+                recipient_a: "carol".into(),
+                recipient_b: "dave".into(),
+            },
+            ExecuteMsg::Reject {},
+        ];
+        for msg in held_msgs {
+            let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg);
+            match res.unwrap_err() {
+                ContractError::CustomError { val } => assert!(val.contains("on hold until")),
+                _ => panic!(),
+            };
+        }
+        // other accounts are unaffected
+        let info = mock_info("bob", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32)).unwrap();
+
+        // expired without an unfreeze call
+        let mut env = mock_env();
+        env.block.time = until;
+        assert_eq!(None, hold_until(deps.as_ref(), env.clone()));
+        let info = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), env, info, Uint128::from(1u32)).unwrap();
+    }
+
+    #[test]
+    fn total_outstanding_saturates() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        // simulate drift: the counter is lower than alice's balance
+        let mut state = STATE.load(&deps.storage).unwrap();
+        state.total_outstanding = Uint128::from(1u32);
+        STATE.save(&mut deps.storage, &state).unwrap();
+
+        let info = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(2u32)).unwrap();
+        assert_eq!(
+            Uint128::zero(),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+    }
+
+    #[test]
+    fn import_balances() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        let entries = vec![
+            BalanceEntry {
+                account: "alice".into(),
+                amount: Uint128::from(3u32),
+            },
+            BalanceEntry {
+                account: "carol".into(),
+                amount: Uint128::from(7u32),
+            },
+        ];
+        let msg = ExecuteMsg::ImportBalances {
+            entries: entries.clone(),
+            denom: None,
+        };
+
+        // only the owner can import
+        let info = mock_info("random", &coins(10, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        // the deposit must match the imported total
+        let info = mock_info("creator", &coins(9, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("please send exactly 10 usei to back the import", val)
+            }
+            _ => panic!(),
+        };
+
+        // duplicates are rejected
+        let mut duplicated = entries.clone();
+        duplicated.push(entries[0].clone());
+        let info = mock_info("creator", &coins(13, "usei"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::ImportBalances {
+                entries: duplicated,
+                denom: None,
+            },
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!("alice is listed more than once", val),
+            _ => panic!(),
+        };
+
+        let info = mock_info("creator", &coins(10, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(
+            Uint128::from(5u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+        assert_eq!(
+            Uint128::from(7u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("carol"))
+                .unwrap()
+        );
+        assert_eq!(
+            Uint128::from(14u32),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+
+        // imported balances can't be rejected back to an earlier sender
+        for account in ["alice", "carol"] {
+            let res = execute_reject(deps.as_mut(), mock_env(), mock_info(account, &[]));
+            match res.unwrap_err() {
+                ContractError::CustomError { val } => assert!(val.contains("can't be determined")),
+                _ => panic!(),
+            };
+        }
+    }
+
+    #[test]
+    fn consolidate_migrated_denom() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let entries = vec![BalanceEntry {
+            account: "alice".into(),
+            amount: Uint128::from(4u32),
+        }];
+        let info = mock_info("creator", &coins(4, "uatom"));
+        let msg = ExecuteMsg::ImportBalances {
+            entries,
+            denom: Some("uatom".into()),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // legacy balances aren't spendable usei
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("alice")));
+        assert!(STATE
+            .load(&deps.storage)
+            .unwrap()
+            .total_outstanding
+            .is_zero());
+
+        // only the owner sets migrations, and only into usei
+        let msg = ExecuteMsg::SetDenomMigration {
+            from_denom: "uatom".into(),
+            to_denom: "usei".into(),
+            rate: Uint128::from(3u32),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            msg.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SetDenomMigration {
+                from_denom: "uatom".into(),
+                to_denom: "uosmo".into(),
+                rate: Uint128::from(3u32),
+            },
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("balances can only be migrated to usei", val)
+            }
+            _ => panic!(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // the credit must be backed by spare usei
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Consolidate {},
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!(
+                "the migration isn't funded. the contract needs 12 more usei",
+                val
+            ),
+            _ => panic!(),
+        };
+
+        deps.querier.update_balance(
+            mock_env().contract.address,
+            vec![coin(12, "usei"), coin(4, "uatom")],
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Consolidate {},
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "credited" && a.value == "12"));
+        assert_eq!(
+            Uint128::from(12u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+        assert_eq!(
+            Uint128::from(12u32),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+        assert!(!LEGACY_BALANCES.has(
+            &deps.storage,
+            (Addr::unchecked("alice"), "uatom".to_string())
+        ));
+
+        // once consolidated the old coins are stray and can be rescued
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::RescueToken {
+                denom: "uatom".into(),
+                recipient: "creator".into(),
+            },
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "amount" && a.value == "4"));
+    }
+
+    #[test]
+    fn consolidate_without_migration() {
+        let mut deps = mock_dependencies_with_balance(&coins(100, "usei"));
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let entries = vec![BalanceEntry {
+            account: "alice".into(),
+            amount: Uint128::from(4u32),
+        }];
+        let info = mock_info("creator", &coins(4, "uatom"));
+        let msg = ExecuteMsg::ImportBalances {
+            entries,
+            denom: Some("uatom".into()),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        deps.querier.update_balance(
+            mock_env().contract.address,
+            vec![coin(100, "usei"), coin(4, "uatom")],
+        );
+
+        for account in ["alice", "bob"] {
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(account, &[]),
+                ExecuteMsg::Consolidate {},
+            );
+            match res.unwrap_err() {
+                ContractError::CustomError { val } => assert_eq!(
+                    "nothing to consolidate. no migration is set for your legacy balances",
+                    val
+                ),
+                _ => panic!(),
+            };
+        }
+        assert_eq!(
+            Uint128::from(4u32),
+            LEGACY_BALANCES
+                .load(
+                    &deps.storage,
+                    (Addr::unchecked("alice"), "uatom".to_string())
+                )
+                .unwrap()
+        );
+
+        // the coins backing the legacy balance can't be rescued
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::RescueToken {
+                denom: "uatom".into(),
+                recipient: "creator".into(),
+            },
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!("no uatom to rescue", val),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn min_deposit() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(3u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::MinDeposit {}).unwrap();
+        let value: MinDepositResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(5u32), value.min_deposit);
+
+        let info = mock_info("sender_a", &coins(4, "usei"));
+        let err = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap_err();
+        assert_eq!(
+            "DepositTooSmall: sent 4 usei but the minimum deposit is 5",
+            err.to_string()
+        );
+
+        // exactly the minimum goes through
+        let info = mock_info("sender_a", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+    }
+
+    #[test]
+    fn transfers_to_recipient() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (recipient_a, recipient_b) in [("bob", "alice"), ("carol", "dave"), ("alice", "bob")] {
+            let info = mock_info("sender", &coins(5, "usei"));
+            execute_transfer(deps.as_mut(), mock_env(), info, recipient_a, recipient_b).unwrap();
+        }
+
+        let transfers_to = |recipient: &str, start_after: Option<u64>, limit: Option<u32>| {
+            let res = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::TransfersToRecipient {
+                    recipient: recipient.into(),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap();
+            let value: TransfersResponse = from_json(&res).unwrap();
+            value
+                .transfers
+                .into_iter()
+                .map(|t| t.id)
+                .collect::<Vec<_>>()
+        };
+
+        // recipient_b in the first transfer and recipient_a in the last
+        assert_eq!(vec![0, 2], transfers_to("alice", None, None));
+        assert_eq!(vec![1], transfers_to("dave", None, None));
+        assert!(transfers_to("sender", None, None).is_empty());
+        assert_eq!(vec![2], transfers_to("alice", Some(0), Some(1)));
+    }
+
+    #[test]
+    fn zero_fee() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::zero(),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the full amount is split and nothing is sent to the owner
+        let info = mock_info("sender", &coins(4, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert!(res.messages.is_empty());
+        for recipient in ["alice", "bob"] {
+            assert_eq!(
+                Uint128::from(2u32),
+                BALANCES
+                    .load(&deps.storage, Addr::unchecked(recipient))
+                    .unwrap()
+            );
+        }
+
+        // the error doesn't mention a fee
+        let info = mock_info("sender", &coins(5, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("invalid funds. please send an even number of usei", val)
+            }
+            _ => panic!(),
+        };
+        let state = STATE.load(&deps.storage).unwrap();
+        match split_funds(&state, Uint128::zero(), None, 2).unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!("please send usei", val),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn forwarding_address() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("alice", &[]);
+        let msg = ExecuteMsg::SetForwardingAddress {
+            addr: "alice".into(),
+        };
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), msg).is_err());
+        let msg = ExecuteMsg::SetForwardingAddress {
+            addr: "carol".into(),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let forwarding = |deps: Deps| -> Option<Addr> {
+            let msg = QueryMsg::ForwardingAddress {
+                account: "alice".into(),
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: ForwardingAddressResponse = from_json(&res).unwrap();
+            value.addr
+        };
+        assert_eq!(Some(Addr::unchecked("carol")), forwarding(deps.as_ref()));
+
+        // alice's share lands on carol
+        let sender = mock_info("sender", &coins(5, "usei"));
+        let res =
+            execute_transfer(deps.as_mut(), mock_env(), sender.clone(), "alice", "bob").unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "recipient_a_balance" && a.value == "2"));
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("alice")));
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("carol"))
+                .unwrap()
+        );
+
+        // cleared
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::ClearForwarding {},
+        )
+        .unwrap();
+        assert_eq!(None, forwarding(deps.as_ref()));
+        execute_transfer(deps.as_mut(), mock_env(), sender, "alice", "bob").unwrap();
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn max_accounts() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            max_accounts: Some(2),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // fill to the cap
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info.clone(), "alice", "bob").unwrap();
+
+        // a new account is rejected
+        let res = execute_transfer(deps.as_mut(), mock_env(), info.clone(), "alice", "carol");
+        match res.unwrap_err() {
+            ContractError::AccountLimitReached { max_accounts } => assert_eq!(2, max_accounts),
+            _ => panic!(),
+        };
+
+        // top-ups still work. the failed transfer isn't reverted in unit tests, so start over
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            max_accounts: Some(2),
+            ..Default::default()
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        execute_transfer(deps.as_mut(), mock_env(), info.clone(), "alice", "bob").unwrap();
+        execute_transfer(deps.as_mut(), mock_env(), info.clone(), "bob", "alice").unwrap();
+        assert_eq!(2, STATE.load(&deps.storage).unwrap().account_count);
+
+        // emptying an account frees a slot
+        let alice = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), alice, Uint128::from(4u32)).unwrap();
+        execute_transfer(deps.as_mut(), mock_env(), info.clone(), "bob", "carol").unwrap();
+
+        // the owner can lift the cap
+        let msg = ExecuteMsg::SetMaxAccounts { max_accounts: None };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            msg.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        execute_transfer(deps.as_mut(), mock_env(), info, "dave", "erin").unwrap();
+        assert_eq!(4, STATE.load(&deps.storage).unwrap().account_count);
+    }
+
+    #[test]
+    fn lifecycle() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let lifecycle = |deps: Deps| -> LifecycleResponse {
+            let res = query(deps, mock_env(), QueryMsg::Lifecycle {}).unwrap();
+            from_json(&res).unwrap()
+        };
+        let value = lifecycle(deps.as_ref());
+        assert_eq!(mock_env().block.time, value.instantiated_at);
+        assert_eq!(None, value.decommissioned_at);
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(60);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Decommission {},
+        )
+        .unwrap();
+        let mut later = env.clone();
+        later.block.time = later.block.time.plus_seconds(60);
+        execute(deps.as_mut(), later, info, ExecuteMsg::Decommission {}).unwrap();
+        assert_eq!(
+            Some(env.block.time),
+            lifecycle(deps.as_ref()).decommissioned_at
+        );
+    }
+
+    #[test]
+    fn transfer_with_referral() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            referral_bps: 2000,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // 11 - fee (1) = 10. 20% (2) goes to the referrer and the other 8 is split
+        let msg = ExecuteMsg::TransferWithReferral {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            referrer: "ref".into(),
+        };
+        let info = mock_info("sender", &coins(11, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+        for (account, balance) in [("alice", 4u32), ("bob", 4), ("ref", 2)] {
+            assert_eq!(
+                Uint128::from(balance),
+                BALANCES
+                    .load(&deps.storage, Addr::unchecked(account))
+                    .unwrap()
+            );
+        }
+        assert_eq!(
+            Uint128::from(10u32),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+
+        // the remainder still has to split evenly. 7 - 1 = 6, the bonus rounds down to 1
+        let msg = ExecuteMsg::TransferWithReferral {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            referrer: "ref".into(),
+        };
+        let info = mock_info("sender", &coins(7, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("invalid funds")),
+            _ => panic!(),
+        };
+
+        // self referrals are rejected
+        let msg = ExecuteMsg::TransferWithReferral {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            referrer: "sender".into(),
+        };
+        let info = mock_info("sender", &coins(11, "usei"));
+        assert!(execute(deps.as_mut(), mock_env(), info, msg).is_err());
+
+        // the bonus is bounded at instantiate
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            referral_bps: 10001,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        assert!(instantiate(deps.as_mut(), mock_env(), info, msg).is_err());
+    }
+
+    #[test]
+    fn normalize_denom() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            normalize_denom: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("sender", &coins(5, "USei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+
+        // the hash of an ibc denom keeps its case
+        let ibc = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+        let normalized = lowercase_denoms(&mock_info("sender", &[coin(5, ibc), coin(5, "USEI")]));
+        assert_eq!(vec![coin(5, ibc), coin(5, "usei")], normalized.funds);
+        let info = mock_info("sender", &coins(5, ibc));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains(ibc)),
+            _ => panic!(),
+        };
+
+        // without the flag the match is exact
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(5, "USEI"));
+        assert!(execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").is_err());
+    }
+
+    #[test]
+    fn flags() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            round_fee_up: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let flags = |deps: Deps| -> FlagsResponse {
+            let res = query(deps, mock_env(), QueryMsg::Flags {}).unwrap();
+            from_json(&res).unwrap()
+        };
+        let value = flags(deps.as_ref());
+        assert!(value.round_fee_up);
+        assert!(value.allow_sender_as_recipient);
+        assert!(!value.fee_holiday);
+        assert!(!value.withdrawals_paused);
+
+        let msg = ExecuteMsg::SetFeeHoliday { enabled: true };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetPause {
+            transfers: None,
+            withdrawals: Some(true),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let value = flags(deps.as_ref());
+        assert!(value.fee_holiday);
+        assert!(value.withdrawals_paused);
+        assert!(!value.transfers_paused);
+    }
+
+    #[test]
+    fn percentage_fee_dust_refund() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::zero(),
+            fee_bps: Some(100),
+            round_fee_up: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // 1% of 150 rounds up to 2, leaving 148 to split
+        let info = mock_info("sender", &coins(150, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(1, res.messages.len());
+        assert!(!res.attributes.iter().any(|a| a.key == "dust_refund"));
+
+        // 1% of 151 rounds up to 2, leaving an odd 149. the extra unit goes back to the sender
+        let info = mock_info("sender", &coins(151, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "sender".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "dust_refund" && a.value == "1"));
+        assert_eq!(
+            Uint128::from(74u32 + 74),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+        assert_eq!(
+            Uint128::from(296u32),
+            STATE.load(&deps.storage).unwrap().total_outstanding
+        );
+    }
+
+    #[test]
+    fn rescue_token() {
+        let mut deps = mock_dependencies_with_balance(&[coin(9, "usei"), coin(4, "uatom")]);
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let rescue = |denom: &str| ExecuteMsg::RescueToken {
+            denom: denom.into(),
+            recipient: "user".into(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            rescue("uatom"),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), rescue("usei"));
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!("usei can't be rescued", val),
+            _ => panic!(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), rescue("uosmo"));
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!("no uosmo to rescue", val),
+            _ => panic!(),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info, rescue("uatom")).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "user".into(),
+                amount: coins(4, "uatom"),
+            })
+        );
+    }
+
+    #[test]
+    fn is_even() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(3u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (amount, expected) in [(7u32, true), (8, false), (3, false), (1, false)] {
+            let msg = QueryMsg::IsEven {
+                amount: Uint128::from(amount),
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let value: IsEvenResponse = from_json(&res).unwrap();
+            assert_eq!(expected, value.even, "{}", amount);
+        }
+    }
+
+    #[test]
+    fn admins() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let owner = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        let is_admin = |deps: Deps, addr: &str| -> bool {
+            let msg = QueryMsg::IsAdmin { addr: addr.into() };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: IsAdminResponse = from_json(&res).unwrap();
+            value.admin
+        };
+        assert!(is_admin(deps.as_ref(), "creator"));
+        assert!(!is_admin(deps.as_ref(), "admin"));
+
+        // non-admins can't update the fee
+        let admin = mock_info("admin", &[]);
+        let update = ExecuteMsg::UpdateSendFee {
+            fee: Uint128::from(3u32),
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin.clone(), update.clone());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        let add = ExecuteMsg::AddAdmin {
+            addr: "admin".into(),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), add).unwrap();
+        assert!(is_admin(deps.as_ref(), "admin"));
+        execute(deps.as_mut(), mock_env(), admin.clone(), update).unwrap();
+        assert_eq!(
+            Uint128::from(3u32),
+            STATE.load(&deps.storage).unwrap().send_fee
+        );
+
+        // only the owner manages admins
+        let add = ExecuteMsg::AddAdmin {
+            addr: "other".into(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin.clone(), add);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        let remove = ExecuteMsg::RemoveAdmin {
+            addr: "admin".into(),
+        };
+        execute(deps.as_mut(), mock_env(), owner, remove).unwrap();
+        assert!(!is_admin(deps.as_ref(), "admin"));
+        let msg = ExecuteMsg::SetFeeHoliday { enabled: true };
+        assert!(execute(deps.as_mut(), mock_env(), admin, msg).is_err());
+    }
+
+    #[test]
+    fn transfer_with_escrow() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let release_code_hash = Binary::from(Sha256::digest(b"secret").to_vec());
+        let msg = ExecuteMsg::TransferWithEscrow {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            release_code_hash,
+        };
+        let info = mock_info("sender", &coins(5, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "escrow_id" && a.value == "0"));
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("bob")));
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(2u32), state.total_outstanding);
+        assert_eq!(Uint128::from(2u32), state.total_escrowed);
+
+        // the wrong preimage keeps the escrow locked
+        let msg = ExecuteMsg::ReleaseEscrow {
+            escrow_id: 0,
+            preimage: Binary::from(b"guess"),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("the preimage doesn't match the release code hash", val)
+            }
+            _ => panic!(),
+        };
+        assert!(ESCROWS.has(&deps.storage, 0));
+
+        let msg = ExecuteMsg::ReleaseEscrow {
+            escrow_id: 0,
+            preimage: Binary::from(b"secret"),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("bob"))
+                .unwrap()
+        );
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(4u32), state.total_outstanding);
+        assert_eq!(Uint128::zero(), state.total_escrowed);
+        assert_eq!(
+            Some(Addr::unchecked("sender")),
+            CREDITED_BY
+                .load(&deps.storage, Addr::unchecked("bob"))
+                .unwrap()
+        );
+
+        // an escrow can only be released once
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("random", &[]), msg).is_err());
+
+        // released into a balance another sender credited, the share can't be rejected to either
+        let msg = ExecuteMsg::TransferWithEscrow {
+            recipient_a: "carol".into(),
+            recipient_b: "dave".into(),
+            release_code_hash: Binary::from(Sha256::digest(b"secret").to_vec()),
+        };
+        let info = mock_info("other", &coins(5, "usei"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "dave", "erin").unwrap();
+        let msg = ExecuteMsg::ReleaseEscrow {
+            escrow_id: 1,
+            preimage: Binary::from(b"secret"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("random", &[]), msg).unwrap();
+        let res = execute_reject(deps.as_mut(), mock_env(), mock_info("dave", &[]));
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("can't be determined")),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn stats() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info.clone(), "alice", "bob").unwrap();
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "carol").unwrap();
+        let alice = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), alice, Uint128::from(3u32)).unwrap();
+        let bob = mock_info("bob", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), bob, Uint128::from(2u32)).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap();
+        let value: StatsResponse = from_json(&res).unwrap();
+        assert_eq!(
+            StatsResponse {
+                transfer_count: 2,
+                total_fees_collected: Uint128::from(2u32),
+                total_outstanding: Uint128::from(3u32),
+                account_count: 2,
+            },
+            value
+        );
+    }
+
+    #[test]
+    fn dust_threshold() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            dust_threshold: Uint128::from(3u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(21, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        // 10 - 6 leaves 4, which is kept
+        let alice = mock_info("alice", &[]);
+        let res = execute_withdraw(
+            deps.as_mut(),
+            mock_env(),
+            alice.clone(),
+            Uint128::from(6u32),
+        )
+        .unwrap();
+        assert_eq!(1, res.messages.len());
+
+        // 4 - 2 would leave 2, which is swept to the owner
+        let res = execute_withdraw(deps.as_mut(), mock_env(), alice, Uint128::from(2u32)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(2, "usei"),
+            })
+        );
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("alice")));
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(10u32), state.total_outstanding);
+        assert_eq!(1, state.account_count);
+    }
+
+    #[test]
+    fn handover() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::Handover {
+            new_owner: "successor".into(),
+            new_fee_recipient: "treasury".into(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            msg.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            _ => panic!(),
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "owner" && a.value == "successor"));
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "fee_recipient" && a.value == "treasury"));
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Addr::unchecked("successor"), state.owner);
+        assert_eq!(Addr::unchecked("treasury"), state.fee_recipient);
+
+        // the previous owner has no rights left
+        assert!(execute(deps.as_mut(), mock_env(), info, msg).is_err());
+    }
+
+    #[test]
+    fn max_recipients() {
+        let max_recipients = |max_recipients: Option<u32>| -> u32 {
+            let mut deps = mock_dependencies();
+            let msg = InstantiateMsg {
+                send_fee: Uint128::from(1u32),
+                max_recipients,
+                ..Default::default()
+            };
+            let info = mock_info("creator", &[]);
+            instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+            let res = query(deps.as_ref(), mock_env(), QueryMsg::MaxRecipients {}).unwrap();
+            let value: MaxRecipientsResponse = from_json(&res).unwrap();
+            value.max_recipients
+        };
+        assert_eq!(3, max_recipients(Some(3)));
+        assert_eq!(DEFAULT_MAX_RECIPIENTS, max_recipients(None));
+
+        // the configured cap is enforced on group transfers
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            max_recipients: Some(2),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetGroup {
+            name: "team".into(),
+            members: vec!["alice".into(), "bob".into(), "carol".into()],
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::TransferToGroup {
+            name: "team".into(),
+        };
+        let info = mock_info("sender", &coins(7, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("a transfer can have at most 2 recipients", val)
+            }
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn auto_pause_on_insolvency() {
+        // the querier only knows about 1 usei
+        let mut deps = mock_dependencies_with_balance(&coins(1, "usei"));
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            verify_solvency_on_withdraw: true,
+            auto_pause_on_insolvency: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(7, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info.clone(), "alice", "bob").unwrap();
+
+        let alice = mock_info("alice", &[]);
+        let res = execute_withdraw(deps.as_mut(), mock_env(), alice, Uint128::from(2u32)).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "auto_paused" && a.value == "true"));
+        assert!(STATE.load(&deps.storage).unwrap().pause.transfers);
+        assert_eq!(
+            Uint128::from(3u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+
+        // no new liabilities while paused
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert_eq!("transfers are paused", val),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn forward_split() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(15, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        // alice's 7 is split: 1 to the owner and 3 each to carol and dave
+        let msg = ExecuteMsg::ForwardSplit {
+            recipient_a: "carol".into(),
+            recipient_b: "dave".into(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1, "usei"),
+            })
+        );
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("alice")));
+        for recipient in ["carol", "dave"] {
+            assert_eq!(
+                Uint128::from(3u32),
+                BALANCES
+                    .load(&deps.storage, Addr::unchecked(recipient))
+                    .unwrap()
+            );
+        }
+
+        // bob's 7 can't be split onward with a fee of 2
+        let mut state = STATE.load(&deps.storage).unwrap();
+        state.send_fee = Uint128::from(2u32);
+        STATE.save(&mut deps.storage, &state).unwrap();
+        let msg = ExecuteMsg::ForwardSplit {
+            recipient_a: "carol".into(),
+            recipient_b: "dave".into(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("even number")),
+            _ => panic!(),
         };
+    }
 
+    #[test]
+    fn forward_split_limits() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            max_withdraw_per_tx: Some(Uint128::from(7u32)),
+            withdraw_cooldown_secs: 60,
+            ..Default::default()
+        };
         let info = mock_info("creator", &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(17, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let forward = ExecuteMsg::ForwardSplit {
+            recipient_a: "carol".into(),
+            recipient_b: "dave".into(),
+        };
 
-        // check owner
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
-        let value: GetOwnerResponse = from_binary(&res).unwrap();
-        assert_eq!("creator", value.owner);
+        // alice's 8 is over the per-transaction cap
+        let info = mock_info("alice", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, forward.clone());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("can withdraw at most 7 per transaction", val)
+            }
+            _ => panic!(),
+        };
 
-        // check send_fee
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetSendFee {}).unwrap();
-        let value: GetSendFeeResponse = from_binary(&res).unwrap();
+        // bob's 7 fits, but not right after a withdrawal
+        let info = mock_info("bob", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32)).unwrap();
+        let info = mock_info("bob", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, forward.clone());
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("cooldown active")),
+            _ => panic!(),
+        };
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(60);
+        let info = mock_info("bob", &[]);
+        execute(deps.as_mut(), env.clone(), info, forward).unwrap();
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("bob")));
+
+        // a partial debit starts the cooldown and counts as activity
+        let msg = ExecuteMsg::WithdrawAndTransfer {
+            amount: Uint128::from(3u32),
+            recipient_a: "carol".into(),
+            recipient_b: "dave".into(),
+        };
+        let info = mock_info("alice", &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        for map in [LAST_WITHDRAW, LAST_ACTIVITY] {
+            assert_eq!(
+                env.block.time,
+                map.load(&deps.storage, Addr::unchecked("alice")).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn attr_prefix() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let msg = ExecuteMsg::SetAttrPrefix {
+            prefix: Some("myapp".into()),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(res.unwrap_err(), ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "myapp_action" && a.value == "set_attr_prefix"));
+
+        let msg = ExecuteMsg::Transfer {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            category: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &coins(4, "usei")),
+            msg,
+        )
+        .unwrap();
+        assert!(res.attributes.iter().all(|a| a.key.starts_with("myapp_")));
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "myapp_action" && a.value == "transfer"));
+
+        let msg = ExecuteMsg::SetAttrPrefix {
+            prefix: Some("my app".into()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg);
+        assert!(matches!(
+            res.unwrap_err(),
+            ContractError::CustomError { .. }
+        ));
+
+        // clearing it restores the plain keys
+        let msg = ExecuteMsg::SetAttrPrefix { prefix: None };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "action"));
+    }
+
+    #[test]
+    fn reconcile_counters() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        let info = mock_info("sender", &coins(10, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        let mut state = STATE.load(&deps.storage).unwrap();
+        state.total_outstanding = Uint128::from(3u32);
+        state.account_count = 7;
+        STATE.save(&mut deps.storage, &state).unwrap();
+
+        let msg = ExecuteMsg::ReconcileCounters {};
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(res.unwrap_err(), ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        for (key, value) in [
+            ("total_outstanding_before", "3"),
+            ("total_outstanding_after", "10"),
+            ("account_count_before", "7"),
+            ("account_count_after", "2"),
+        ] {
+            assert!(res
+                .attributes
+                .iter()
+                .any(|a| a.key == key && a.value == value));
+        }
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(10u32), state.total_outstanding);
+        assert_eq!(2, state.account_count);
+    }
+
+    #[test]
+    fn schema_version() {
+        let deps = mock_dependencies();
+        // answered without state, so it works on any deployment
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::SchemaVersion {}).unwrap();
+        let value: SchemaVersionResponse = from_json(&res).unwrap();
+        assert_eq!(1, value.msg_version);
+    }
+
+    #[test]
+    fn require_registered_recipients() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            require_registered_recipients: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Register {},
+        )
+        .unwrap();
+
+        let registered = |deps: Deps, addr: &str| {
+            let msg = QueryMsg::IsRegistered { addr: addr.into() };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: IsRegisteredResponse = from_json(&res).unwrap();
+            value.registered
+        };
+        assert!(registered(deps.as_ref(), "alice"));
+        assert!(!registered(deps.as_ref(), "bob"));
+
+        let info = mock_info("sender", &coins(4, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob");
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("bob hasn't registered to receive transfers", val)
+            }
+            _ => panic!(),
+        };
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::Register {},
+        )
+        .unwrap();
+        let info = mock_info("sender", &coins(4, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(
+            Uint128::from(2u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("bob"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn freeze_many() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        let info = mock_info("sender", &coins(10, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let info = mock_info("sender", &coins(10, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "carol", "dave").unwrap();
+
+        let msg = ExecuteMsg::FreezeMany {
+            accounts: vec!["alice".into(), "bob".into(), "carol".into()],
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(res.unwrap_err(), ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "count" && a.value == "3"));
+
+        for account in ["alice", "bob", "carol"] {
+            assert!(FROZEN.has(&deps.storage, Addr::unchecked(account)));
+            let info = mock_info(account, &[]);
+            let res = execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32));
+            match res.unwrap_err() {
+                ContractError::CustomError { val } => assert_eq!("account is frozen", val),
+                _ => panic!(),
+            };
+        }
+        let info = mock_info("dave", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32)).unwrap();
+
+        let msg = ExecuteMsg::FreezeMany {
+            accounts: vec!["x".into(); MAX_BATCH_ACCOUNTS + 1],
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg);
+        assert!(matches!(
+            res.unwrap_err(),
+            ContractError::CustomError { .. }
+        ));
+
+        let msg = ExecuteMsg::UnfreezeMany {
+            accounts: vec!["alice".into(), "bob".into()],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert!(!FROZEN.has(&deps.storage, Addr::unchecked("alice")));
+        assert!(FROZEN.has(&deps.storage, Addr::unchecked("carol")));
+        let info = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32)).unwrap();
+    }
+
+    #[test]
+    fn withdrawable_at() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            withdraw_cooldown_secs: 100,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(10, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        let withdrawable_at = |deps: Deps, env: Env, account: &str| {
+            let msg = QueryMsg::WithdrawableAt {
+                account: account.into(),
+            };
+            let res = query(deps, env, msg).unwrap();
+            let value: WithdrawableAtResponse = from_json(&res).unwrap();
+            value.at
+        };
+        assert_eq!(None, withdrawable_at(deps.as_ref(), mock_env(), "alice"));
+
+        let info = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(1u32)).unwrap();
+        let next = mock_env().block.time.plus_seconds(100);
+        assert_eq!(
+            Some(next),
+            withdrawable_at(deps.as_ref(), mock_env(), "alice")
+        );
+        assert_eq!(None, withdrawable_at(deps.as_ref(), mock_env(), "bob"));
+
+        // a longer hold pushes it back, a shorter one doesn't
+        let until = mock_env().block.time.plus_seconds(50);
+        let msg = ExecuteMsg::HoldAccount {
+            account: "alice".into(),
+            until,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            Some(next),
+            withdrawable_at(deps.as_ref(), mock_env(), "alice")
+        );
+        let until = mock_env().block.time.plus_seconds(200);
+        let msg = ExecuteMsg::HoldAccount {
+            account: "alice".into(),
+            until,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            Some(until),
+            withdrawable_at(deps.as_ref(), mock_env(), "alice")
+        );
+
+        let mut env = mock_env();
+        env.block.time = until;
+        assert_eq!(None, withdrawable_at(deps.as_ref(), env, "alice"));
+    }
+
+    #[test]
+    fn transfer_weighted() {
+        assert_eq!(
+            vec![
+                Uint128::from(6u32),
+                Uint128::from(2u32),
+                Uint128::from(2u32)
+            ],
+            weighted_shares(Uint128::from(10u32), &[3, 1, 1])
+        );
+        // 7 * 1/3 each floors to 2, and the one left over goes to the first of the tied weights
+        assert_eq!(
+            vec![
+                Uint128::from(3u32),
+                Uint128::from(2u32),
+                Uint128::from(2u32)
+            ],
+            weighted_shares(Uint128::from(7u32), &[1, 1, 1])
+        );
+        // the heaviest weight gets the leftover even when it comes last
+        assert_eq!(
+            vec![
+                Uint128::from(1u32),
+                Uint128::from(1u32),
+                Uint128::from(3u32)
+            ],
+            weighted_shares(Uint128::from(5u32), &[1, 1, 2])
+        );
+        for amount in [1u32, 7, 100, 1001] {
+            let shares = weighted_shares(Uint128::from(amount), &[5, 3, 2, 7]);
+            assert_eq!(Uint128::from(amount), shares.iter().sum::<Uint128>());
+        }
+
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::TransferWeighted {
+            recipients: vec![
+                WeightedRecipient {
+                    addr: "alice".into(),
+                    weight: 1,
+                },
+                WeightedRecipient {
+                    addr: "bob".into(),
+                    weight: 2,
+                },
+            ],
+        };
+        let info = mock_info("sender", &coins(11, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // 10 after the fee: alice 3, bob 6 and the leftover unit to bob
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            Uint128::from(3u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+        assert_eq!(
+            Uint128::from(7u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("bob"))
+                .unwrap()
+        );
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(10u32), state.total_outstanding);
+        assert_eq!(0, state.transfer_count);
+
+        let msg = ExecuteMsg::TransferWeighted {
+            recipients: vec![WeightedRecipient {
+                addr: "alice".into(),
+                weight: 0,
+            }],
+        };
+        let info = mock_info("sender", &coins(11, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(matches!(
+            res.unwrap_err(),
+            ContractError::CustomError { .. }
+        ));
+    }
+
+    #[test]
+    fn claim_for() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        let info = mock_info("sender", &coins(10, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let info = mock_info("sender", &coins(6, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "carol", "dave").unwrap();
+        let msg = ExecuteMsg::SetWithdrawalAddress {
+            addr: "bob_wallet".into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::ClaimFor {
+            accounts: vec![
+                "alice".into(),
+                "bob".into(),
+                "carol".into(),
+                "nobody".into(),
+            ],
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(res.unwrap_err(), ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "claimed" && a.value == "3"));
+        let sends: Vec<_> = res.messages.iter().map(|m| m.msg.clone()).collect();
+        assert_eq!(
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "alice".into(),
+                    amount: coins(5, "usei"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "bob_wallet".into(),
+                    amount: coins(5, "usei"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "carol".into(),
+                    amount: coins(3, "usei"),
+                }),
+            ],
+            sends
+        );
+        for account in ["alice", "bob", "carol"] {
+            assert!(!BALANCES.has(&deps.storage, Addr::unchecked(account)));
+        }
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(3u32), state.total_outstanding);
+        assert_eq!(1, state.account_count);
+    }
+
+    #[test]
+    fn claim_for_limits() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            max_withdraw_per_tx: Some(Uint128::from(10u32)),
+            withdraw_cooldown_secs: 60,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(2000, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        for (a, b) in [("carol", "carol"), ("dave", "dave")] {
+            let info = mock_info("sender", &coins(8, "usei"));
+            execute_transfer(deps.as_mut(), mock_env(), info, a, b).unwrap();
+        }
+        let msg = ExecuteMsg::SetWithdrawLimit {
+            account: "carol".into(),
+            limit: Some(Uint128::from(5u32)),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // alice is over the per-transaction cap and carol over her daily limit
+        let claim = ExecuteMsg::ClaimFor {
+            accounts: vec!["alice".into(), "carol".into(), "dave".into()],
+        };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, claim.clone()).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "claimed" && a.value == "1"));
+        assert_eq!(
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                to_address: "dave".into(),
+                amount: coins(8, "usei"),
+            })],
+            res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Uint128::from(1000u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+
+        // the claim starts the cooldown like any withdrawal
+        let info = mock_info("sender", &coins(8, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "dave", "dave").unwrap();
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, claim).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn overpayment_refund() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            overpayment_refund: true,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let members = |n: usize| (0..n).map(|i| format!("member{}", i)).collect::<Vec<_>>();
+        for (name, size) in [("three", 3), ("twelve", 12)] {
+            let msg = ExecuteMsg::SetGroup {
+                name: name.into(),
+                members: members(size),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        }
+
+        // 1 fee + 3 * 3 + 2 over, and 1 fee + 12 * 2 + 11 over
+        for (name, funds, share, excess) in [("three", 12, "3", 2), ("twelve", 36, "2", 11)] {
+            let msg = ExecuteMsg::TransferToGroup { name: name.into() };
+            let info = mock_info("sender", &coins(funds, "usei"));
+            let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+            assert!(res
+                .attributes
+                .iter()
+                .any(|a| a.key == "share" && a.value == share));
+            assert!(res
+                .attributes
+                .iter()
+                .any(|a| a.key == "dust_refund" && a.value == excess.to_string()));
+            assert_eq!(
+                res.messages[1].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "sender".into(),
+                    amount: coins(excess, "usei"),
+                })
+            );
+        }
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(33u32), state.total_outstanding);
+
+        // without the option the odd deposit is still rejected
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(6, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob");
+        assert!(matches!(
+            res.unwrap_err(),
+            ContractError::CustomError { .. }
+        ));
+    }
+
+    #[test]
+    fn simulate_transfer_msg() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::SetForwardingAddress {
+            addr: "bob_vault".into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetGroup {
+            name: "team".into(),
+            members: vec!["alice".into(), "bob".into(), "carol".into()],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let simulate = |deps: Deps, msg: ExecuteMsg, amount: u32| {
+            let msg = QueryMsg::SimulateTransferMsg {
+                msg: Box::new(msg),
+                amount: Uint128::from(amount),
+            };
+            query(deps, mock_env(), msg).map(|res| {
+                let value: SimulateTransferMsgResponse = from_json(&res).unwrap();
+                value
+            })
+        };
+        let credit = |addr: &str, amount: u32| SimulatedCredit {
+            addr: Addr::unchecked(addr),
+            amount: Uint128::from(amount),
+        };
+
+        let msg = ExecuteMsg::Transfer {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            category: None,
+        };
+        let value = simulate(deps.as_ref(), msg.clone(), 7).unwrap();
+        assert_eq!(
+            SimulateTransferMsgResponse {
+                credits: vec![credit("alice", 3), credit("bob_vault", 3)],
+                fee: Uint128::from(1u32),
+                refund: Uint128::zero(),
+            },
+            value
+        );
+        assert!(simulate(deps.as_ref(), msg, 6).is_err());
+
+        // there is no TransferMany, so the many-recipient variant is the group transfer
+        let msg = ExecuteMsg::TransferToGroup {
+            name: "team".into(),
+        };
+        let value = simulate(deps.as_ref(), msg, 10).unwrap();
+        assert_eq!(
+            vec![
+                credit("alice", 3),
+                credit("bob_vault", 3),
+                credit("carol", 3)
+            ],
+            value.credits
+        );
         assert_eq!(Uint128::from(1u32), value.fee);
 
-        // check balance of nonexistent account
-        let res = query(
+        // distributing needs existing holders, which are the weights
+        let res = simulate(
+            deps.as_ref(),
+            ExecuteMsg::DistributePro {
+                start_after: None,
+                limit: None,
+            },
+            7,
+        );
+        assert!(res.unwrap_err().to_string().contains("no balance holders"));
+        let info = mock_info("sender", &coins(9, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "carol").unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "alice").unwrap();
+        let value = simulate(
             deps.as_ref(),
+            ExecuteMsg::DistributePro {
+                start_after: None,
+                limit: None,
+            },
+            7,
+        )
+        .unwrap();
+        assert_eq!(vec![credit("alice", 4), credit("carol", 2)], value.credits);
+
+        let res = simulate(deps.as_ref(), ExecuteMsg::Register {}, 10);
+        assert!(res
+            .unwrap_err()
+            .to_string()
+            .contains("not a transfer message"));
+    }
+
+    #[test]
+    fn require_memo() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        let transfer = ExecuteMsg::Transfer {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            category: None,
+        };
+        let with_memo = |memo: &str| ExecuteMsg::TransferWithMemo {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            memo: memo.into(),
+        };
+
+        // not required: a missing memo is fine
+        let info = mock_info("sender", &coins(4, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, transfer.clone()).unwrap();
+        assert!(!res.attributes.iter().any(|a| a.key == "memo"));
+        let info = mock_info("sender", &coins(4, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, with_memo("invoice 7")).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "memo" && a.value == "invoice 7"));
+
+        let msg = ExecuteMsg::SetRequireMemo { required: true };
+        let res = execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBalance {
-                account: "random".into(),
+            mock_info("random", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(res.unwrap_err(), ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // required: a missing or empty memo is rejected
+        let group = ExecuteMsg::TransferToGroup {
+            name: "team".into(),
+        };
+        let referral = ExecuteMsg::TransferWithReferral {
+            recipient_a: "alice".into(),
+            recipient_b: "bob".into(),
+            referrer: "carol".into(),
+        };
+        for msg in [
+            transfer,
+            with_memo(""),
+            group,
+            referral,
+            ExecuteMsg::DistributePro {
+                start_after: None,
+                limit: None,
+            },
+        ] {
+            let info = mock_info("sender", &coins(4, "usei"));
+            let res = execute(deps.as_mut(), mock_env(), info, msg);
+            assert!(matches!(res.unwrap_err(), ContractError::MemoRequired {}));
+        }
+        let info = mock_info("sender", &coins(4, "usei"));
+        execute(deps.as_mut(), mock_env(), info, with_memo("invoice 8")).unwrap();
+        assert_eq!(
+            Uint128::from(6u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn owner_earnings() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(2u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        for _ in 0..3 {
+            let info = mock_info("sender", &coins(6, "usei"));
+            execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        }
+        let info = mock_info("creator", &coins(12, "usei"));
+        execute_fund_fee_pool(deps.as_mut(), info).unwrap();
+        let msg = ExecuteMsg::ClaimFees {
+            amount: Some(Uint128::from(4u32)),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::OwnerEarnings {}).unwrap();
+        let value: OwnerEarningsResponse = from_json(&res).unwrap();
+        assert_eq!(
+            OwnerEarningsResponse {
+                claimable: Uint128::from(8u32),
+                lifetime: Uint128::from(6u32),
             },
-        )
-        .unwrap();
-        let value: GetBalanceResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::from(0u32), value.balance);
+            value
+        );
     }
 
     #[test]
-    fn send_coins_negative_path() {
-        // init the contract
+    fn fee_to_recipients() {
         let mut deps = mock_dependencies();
         let msg = InstantiateMsg {
-            send_fee: Uint128::from(1u32),
+            send_fee: Uint128::from(2u32),
+            fee_to_recipients: true,
+            ..Default::default()
         };
         let info = mock_info("creator", &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        // negative path: send the wrong type of coin
-        let info = mock_info("sender_a", &coins(1, "BTC"));
-        let res = execute_transfer(deps.as_mut(), info, "recipient_a", "recipient_b");
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::CustomError { val } => assert!(val.contains("invalid denomination")),
-            _ => assert!(false),
-        };
+        let info = mock_info("sender", &coins(10, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert!(res.messages.is_empty());
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "fee_to_recipients" && a.value == "2"));
+        for recipient in ["alice", "bob"] {
+            assert_eq!(
+                Uint128::from(5u32),
+                BALANCES
+                    .load(&deps.storage, Addr::unchecked(recipient))
+                    .unwrap()
+            );
+        }
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(10u32), state.total_outstanding);
+        assert_eq!(Uint128::zero(), state.total_fees_collected);
+        assert_eq!(Uint128::zero(), HISTORY.load(&deps.storage, 0).unwrap().fee);
 
-        // negative path: send multiple types of coin
-        let to_send = vec![coin(1, "usei"), coin(1, "usei")];
-        let info = mock_info("sender_a", &to_send);
-        let res = execute_transfer(deps.as_mut(), info, "recipient_a", "recipient_b");
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::CustomError { val } => assert!(val.as_str() == "please only send usei"),
-            _ => assert!(false),
+        // the queries agree with what the recipients get
+        let msg = QueryMsg::ShareFor {
+            amount: Uint128::from(10u32),
         };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: ShareForResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(5u32), value.share);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::MinDeposit {}).unwrap();
+        let value: MinDepositResponse = from_json(&res).unwrap();
+        assert_eq!(Uint128::from(2u32), value.min_deposit);
+        let info = mock_info("sender", &coins(2, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(
+            Uint128::from(6u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+    }
 
-        // negative path: send no coins
-        let info = mock_info("sender_a", &[]);
-        let res = execute_transfer(deps.as_mut(), info, "recipient_a", "recipient_b");
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::CustomError { val } => assert!(val.as_str() == "please send usei"),
-            _ => assert!(false),
-        };
+    #[test]
+    fn smallest_holders() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        for (a, b, funds) in [
+            ("alice", "bob", 20),
+            ("carol", "dave", 6),
+            ("erin", "frank", 14),
+        ] {
+            let info = mock_info("sender", &coins(funds, "usei"));
+            execute_transfer(deps.as_mut(), mock_env(), info, a, b).unwrap();
+        }
+        let info = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(9u32)).unwrap();
 
-        // negative path: send the wrong number of coins (odd number greater than fee)
-        // 4 - fee (1) = 3, which is not divisible by 2
-        let info = mock_info("sender_a", &coins(4, "usei"));
-        let res = execute_transfer(deps.as_mut(), info, "recipient_a", "recipient_b");
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::CustomError { val } => assert!(val.contains("invalid funds")),
-            _ => assert!(false),
+        let smallest = |limit: u32| {
+            let msg = QueryMsg::SmallestHolders { limit };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let value: HoldersResponse = from_json(&res).unwrap();
+            value
+                .holders
+                .into_iter()
+                .map(|h| (h.account.to_string(), h.balance.u128()))
+                .collect::<Vec<_>>()
         };
+        // equal balances come out in address order
+        assert_eq!(
+            vec![
+                ("alice".to_string(), 1),
+                ("carol".to_string(), 3),
+                ("dave".to_string(), 3),
+            ],
+            smallest(3)
+        );
+        assert_eq!(6, smallest(100).len());
+        assert_eq!(("bob".to_string(), 10), smallest(100)[5]);
+        assert!(smallest(0).is_empty());
+    }
 
-        // negative path: send the wrong number of coins (just send the fee)
-        let info = mock_info("sender_a", &coins(1, "usei"));
-        let res = execute_transfer(deps.as_mut(), info, "recipient_a", "recipient_b");
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::CustomError { val } => assert!(val.contains("funds <= fee")),
-            _ => assert!(false),
+    #[test]
+    fn daily_withdraw_limit() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        let info = mock_info("sender", &coins(40, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+
+        let msg = ExecuteMsg::SetWithdrawLimit {
+            account: "alice".into(),
+            limit: Some(Uint128::from(10u32)),
         };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("random", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(res.unwrap_err(), ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
 
-        // negative path: send the wrong number of coins (zero)
-        let info = mock_info("sender_a", &coins(0, "usei"));
-        let res = execute_transfer(deps.as_mut(), info, "recipient_a", "recipient_b");
-        assert!(res.is_err());
+        // within the limit, in two withdrawals on the same day
+        let withdraw = |deps: DepsMut, env: Env, account: &str, amount: u32| {
+            let info = mock_info(account, &[]);
+            execute_withdraw(deps, env, info, Uint128::from(amount))
+        };
+        withdraw(deps.as_mut(), mock_env(), "alice", 6).unwrap();
+        let mut later = mock_env();
+        later.block.time = later.block.time.plus_seconds(60);
+        withdraw(deps.as_mut(), later.clone(), "alice", 4).unwrap();
+        // over the limit
+        match withdraw(deps.as_mut(), later.clone(), "alice", 1).unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("daily withdraw limit of 10 reached. 0 left today", val)
+            }
+            _ => panic!(),
+        };
+        // splitting onward is a withdrawal too
+        let split = |amount: u32| ExecuteMsg::WithdrawAndTransfer {
+            amount: Uint128::from(amount),
+            recipient_a: "carol".into(),
+            recipient_b: "dave".into(),
+        };
+        let info = mock_info("alice", &[]);
+        let res = execute(deps.as_mut(), later.clone(), info, split(2));
         match res.unwrap_err() {
-            ContractError::CustomError { val } => assert!(val.contains("funds <= fee")),
-            _ => assert!(false),
+            ContractError::CustomError { val } => assert!(val.contains("daily withdraw limit")),
+            _ => panic!(),
         };
+        // alice can withdraw again at the start of the next day
+        let msg = QueryMsg::WithdrawableAt {
+            account: "alice".into(),
+        };
+        let res = query(deps.as_ref(), later.clone(), msg).unwrap();
+        let value: WithdrawableAtResponse = from_json(&res).unwrap();
+        let day = later.block.time.seconds() / SECONDS_PER_DAY;
+        assert_eq!(
+            Some(Timestamp::from_seconds((day + 1) * SECONDS_PER_DAY)),
+            value.at
+        );
+        // accounts without a limit are unaffected
+        withdraw(deps.as_mut(), later, "bob", 20).unwrap();
+
+        // the next day starts from zero again
+        let mut next_day = mock_env();
+        next_day.block.time = next_day.block.time.plus_seconds(SECONDS_PER_DAY);
+        assert!(withdraw(deps.as_mut(), next_day.clone(), "alice", 11).is_err());
+        withdraw(deps.as_mut(), next_day.clone(), "alice", 10).unwrap();
+
+        // a split counts against the same limit as a withdrawal
+        let info = mock_info("sender", &coins(40, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let mut day_after = next_day;
+        day_after.block.time = day_after.block.time.plus_seconds(SECONDS_PER_DAY);
+        let info = mock_info("alice", &[]);
+        execute(deps.as_mut(), day_after.clone(), info, split(6)).unwrap();
+        assert!(withdraw(deps.as_mut(), day_after.clone(), "alice", 5).is_err());
+        withdraw(deps.as_mut(), day_after, "alice", 4).unwrap();
     }
 
     #[test]
-    fn send_coins() {
-        // init the contract
+    fn reclaim_inactive() {
         let mut deps = mock_dependencies();
         let msg = InstantiateMsg {
-            send_fee: Uint128::from(1u32),
+            inactivity_period_secs: Some(1000),
+            ..Default::default()
         };
         let info = mock_info("creator", &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("sender", &coins(10, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
 
-        // send coins to the same address
-        let info = mock_info("sender_a", &coins(3, "usei"));
-        let res = execute_transfer(deps.as_mut(), info, "recipient_a", "recipient_a").unwrap();
-        // verify the creator was paid
-        assert!(res.messages.len() == 1);
-        assert_eq!(
-            res.messages[0].msg,
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address: "creator".into(),
-                amount: coins(1, "usei"),
-            })
+        // bob withdraws later, which counts as activity
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(600);
+        let info = mock_info("bob", &[]);
+        execute_withdraw(deps.as_mut(), env, info, Uint128::from(1u32)).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1001);
+        let reclaim = |account: &str| ExecuteMsg::ReclaimInactive {
+            account: account.into(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("random", &[]),
+            reclaim("alice"),
         );
+        assert!(matches!(res.unwrap_err(), ContractError::Unauthorized {}));
 
-        // send coins to different addresses
-        let info = mock_info("sender_a", &coins(7, "usei"));
-        let res = execute_transfer(deps.as_mut(), info, "recipient_b", "recipient_c").unwrap();
-        // verify the creator was paid
-        assert!(res.messages.len() == 1);
+        // active
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            reclaim("bob"),
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("within the inactivity")),
+            _ => panic!(),
+        };
+
+        // long inactive
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("creator", &[]),
+            reclaim("alice"),
+        )
+        .unwrap();
         assert_eq!(
             res.messages[0].msg,
             CosmosMsg::Bank(BankMsg::Send {
                 to_address: "creator".into(),
-                amount: coins(1, "usei"),
+                amount: coins(5, "usei"),
             })
         );
+        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("alice")));
+        assert!(!LAST_ACTIVITY.has(&deps.storage, Addr::unchecked("alice")));
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(4u32), state.total_outstanding);
+    }
 
-        // query balances of recipients
-        let res = query(
-            deps.as_ref(),
+    #[test]
+    fn distribute_pro() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(1u32),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("sender", &coins(11, "usei"));
+        let res = execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBalance {
-                account: "recipient_a".into(),
+            info,
+            ExecuteMsg::DistributePro {
+                start_after: None,
+                limit: None,
             },
-        )
-        .unwrap();
-        let value: GetBalanceResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::from(2u32), value.balance);
+        );
+        assert!(matches!(
+            res.unwrap_err(),
+            ContractError::CustomError { .. }
+        ));
 
-        let res = query(
-            deps.as_ref(),
+        // alice 3, bob 1, carol 2
+        let info = mock_info("sender", &coins(3, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        let info = mock_info("sender", &coins(5, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "carol").unwrap();
+
+        // 10 after the fee: 5, 1 and 3, plus the leftover unit to alice
+        let info = mock_info("sender", &coins(11, "usei"));
+        let res = execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBalance {
-                account: "recipient_b".into(),
+            info,
+            ExecuteMsg::DistributePro {
+                start_after: None,
+                limit: None,
             },
         )
         .unwrap();
-        let value: GetBalanceResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::from(3u32), value.balance);
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "holders" && a.value == "3"));
+        for (holder, balance) in [("alice", 9u32), ("bob", 2), ("carol", 5)] {
+            assert_eq!(
+                Uint128::from(balance),
+                BALANCES
+                    .load(&deps.storage, Addr::unchecked(holder))
+                    .unwrap()
+            );
+        }
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(16u32), state.total_outstanding);
 
-        let res = query(
-            deps.as_ref(),
+        // nothing left to split once the fee is taken
+        let info = mock_info("sender", &coins(1, "usei"));
+        let res = execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBalance {
-                account: "recipient_c".into(),
+            info,
+            ExecuteMsg::DistributePro {
+                start_after: None,
+                limit: None,
             },
-        )
-        .unwrap();
-        let value: GetBalanceResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::from(3u32), value.balance);
+        );
+        assert!(res.is_err());
     }
 
     #[test]
-    fn withdraw_coins() {
-        // init the contract
+    fn distribute_pro_skips_holders() {
         let mut deps = mock_dependencies();
         let msg = InstantiateMsg {
-            send_fee: Uint128::from(1u32),
+            allow_sender_as_recipient: Some(false),
+            ..Default::default()
         };
         let info = mock_info("creator", &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // big 999,999, dist 2 and tiny 1
+        let info = mock_info("sender", &coins(2, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "tiny", "big").unwrap();
+        let info = mock_info("sender", &coins(999_998, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "big", "big").unwrap();
+        let info = mock_info("sender", &coins(2, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "dist", "dist").unwrap();
+        let balance = |deps: Deps, holder: &str| {
+            BALANCES
+                .load(deps.storage, Addr::unchecked(holder))
+                .unwrap()
+                .u128()
+        };
+        let distribute =
+            |start_after: Option<&str>, limit: Option<u32>| ExecuteMsg::DistributePro {
+                start_after: start_after.map(String::from),
+                limit,
+            };
 
-        // send coins
-        let info = mock_info("sender_a", &coins(7, "usei"));
-        execute_transfer(deps.as_mut(), info, "recipient_a", "recipient_b").unwrap();
+        // the sender can't be a recipient, and tiny's share rounds to nothing
+        let info = mock_info("dist", &coins(1000, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, distribute(None, None)).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "holders" && a.value == "2"));
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "last_holder" && a.value == "tiny"));
+        assert_eq!(1_000_999, balance(deps.as_ref(), "big"));
+        assert_eq!(2, balance(deps.as_ref(), "dist"));
+        assert_eq!(1, balance(deps.as_ref(), "tiny"));
 
-        // query balance
-        let res = query(
-            deps.as_ref(),
+        // one page at a time
+        let info = mock_info("dist", &coins(10, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, distribute(None, Some(1))).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "last_holder" && a.value == "big"));
+        assert_eq!(1_001_009, balance(deps.as_ref(), "big"));
+        let info = mock_info("dist", &coins(10, "usei"));
+        let res = execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBalance {
-                account: "recipient_a".into(),
-            },
+            info,
+            distribute(Some("big"), Some(1)),
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => assert!(val.contains("none of these")),
+            _ => panic!(),
+        };
+        let info = mock_info("dist", &coins(10, "usei"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            distribute(Some("dist"), Some(1)),
         )
         .unwrap();
-        let value: GetBalanceResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::from(3u32), value.balance);
+        assert_eq!(11, balance(deps.as_ref(), "tiny"));
+    }
 
-        // withdraw using account not listed
-        let info = mock_info("random", &[]);
-        let res = execute_withdraw(deps.as_mut(), info, Uint128::from(1u32));
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::Unauthorized {} => {}
-            _ => assert!(false),
-        };
+    #[test]
+    fn counter_drift() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        let info = mock_info("sender", &coins(10, "usei"));
+        execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
 
-        // withdraw too many
-        let info = mock_info("recipient_a", &[]);
-        let res = execute_withdraw(deps.as_mut(), info, Uint128::from(4u32));
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::CustomError { val } => assert!(val.contains("insufficient funds")),
-            _ => assert!(false),
+        let drift = |deps: Deps| {
+            let res = query(deps, mock_env(), QueryMsg::CounterDrift {}).unwrap();
+            let value: CounterDriftResponse = from_json(&res).unwrap();
+            value
         };
+        assert!(!drift(deps.as_ref()).drift);
 
-        // send money with withdrawal request
-        let info = mock_info("recipient_a", &coins(1, "usei"));
-        let res = execute_withdraw(deps.as_mut(), info, Uint128::from(4u32));
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::CustomError { val } => assert!(val.contains("no funds required")),
-            _ => assert!(false),
-        };
+        let mut state = STATE.load(&deps.storage).unwrap();
+        state.account_count = 5;
+        STATE.save(&mut deps.storage, &state).unwrap();
+        assert_eq!(
+            CounterDriftResponse {
+                stored_total: Uint128::from(10u32),
+                computed_total: Uint128::from(10u32),
+                stored_count: 5,
+                computed_count: 2,
+                drift: true,
+            },
+            drift(deps.as_ref())
+        );
 
-        // withdraw less than total
-        let info = mock_info("recipient_a", &[]);
-        let res = execute_withdraw(deps.as_mut(), info, Uint128::from(2u32)).unwrap();
+        // reconciling clears it
+        let msg = ExecuteMsg::ReconcileCounters {};
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert!(!drift(deps.as_ref()).drift);
+    }
 
-        // verify the recipient was paid
-        assert!(res.messages.len() == 1);
+    #[test]
+    fn percentage_fee_clamp() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            fee_bps: Some(1000),
+            min_fee: Uint128::from(2u32),
+            max_fee: Some(Uint128::from(10u32)),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let state = STATE.load(&deps.storage).unwrap();
+        // the floor, the proportional middle and the cap
+        for (funds, fee) in [(10u32, 2u32), (60, 6), (1000, 10)] {
+            assert_eq!(
+                Uint128::from(fee),
+                compute_fee(&state, Uint128::from(funds), None)
+            );
+        }
+        let info = mock_info("sender", &coins(1000, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
         assert_eq!(
             res.messages[0].msg,
             CosmosMsg::Bank(BankMsg::Send {
-                to_address: "recipient_a".into(),
-                amount: coins(2, "usei"),
+                to_address: "creator".into(),
+                amount: coins(10, "usei"),
             })
         );
+        assert_eq!(
+            Uint128::from(495u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
 
-        // query balance
-        let res = query(
-            deps.as_ref(),
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            fee_bps: Some(1000),
+            min_fee: Uint128::from(5u32),
+            max_fee: Some(Uint128::from(4u32)),
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("min_fee must not be above max_fee", val)
+            }
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn balances_by_category() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        let transfer = |a: &str, b: &str, category: Option<&str>| ExecuteMsg::Transfer {
+            recipient_a: a.into(),
+            recipient_b: b.into(),
+            category: category.map(String::from),
+        };
+        let info = mock_info("sender", &coins(10, "usei"));
+        let res = execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBalance {
-                account: "recipient_a".into(),
-            },
+            info,
+            transfer("bob", "alice", Some("payroll")),
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "category" && a.value == "payroll"));
+        let info = mock_info("sender", &coins(10, "usei"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            transfer("carol", "dave", None),
         )
         .unwrap();
-        let value: GetBalanceResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::from(1u32), value.balance);
-
-        // verify that recipient_a is within BALANCES, as this will be checked later
-        assert!(BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
 
-        // withdraw remaining
-        let info = mock_info("recipient_a", &[]);
-        let res = execute_withdraw(deps.as_mut(), info, Uint128::from(1u32)).unwrap();
+        let by_category = |deps: Deps, category: &str| {
+            let msg = QueryMsg::BalancesByCategory {
+                category: category.into(),
+                start_after: None,
+                limit: None,
+            };
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: AccountsResponse = from_json(&res).unwrap();
+            value.accounts
+        };
+        assert_eq!(
+            vec![Addr::unchecked("alice"), Addr::unchecked("bob")],
+            by_category(deps.as_ref(), "payroll")
+        );
+        assert!(by_category(deps.as_ref(), "grants").is_empty());
 
-        // verify the recipient was paid
-        assert!(res.messages.len() == 1);
+        // a later category replaces the earlier one, and closing the balance removes it
+        let info = mock_info("sender", &coins(10, "usei"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            transfer("bob", "carol", Some("grants")),
+        )
+        .unwrap();
         assert_eq!(
-            res.messages[0].msg,
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address: "recipient_a".into(),
-                amount: coins(1, "usei"),
-            })
+            vec![Addr::unchecked("alice")],
+            by_category(deps.as_ref(), "payroll")
+        );
+        let info = mock_info("alice", &[]);
+        execute_withdraw(deps.as_mut(), mock_env(), info, Uint128::from(5u32)).unwrap();
+        assert!(by_category(deps.as_ref(), "payroll").is_empty());
+        assert_eq!(
+            vec![Addr::unchecked("bob"), Addr::unchecked("carol")],
+            by_category(deps.as_ref(), "grants")
         );
 
-        // query balance
-        let res = query(
-            deps.as_ref(),
+        // an empty category is the same as none and keeps the existing tags
+        let info = mock_info("sender", &coins(10, "usei"));
+        let res = execute(
+            deps.as_mut(),
             mock_env(),
-            QueryMsg::GetBalance {
-                account: "recipient_a".into(),
-            },
+            info,
+            transfer("bob", "carol", Some("")),
         )
         .unwrap();
-        let value: GetBalanceResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::from(0u32), value.balance);
+        assert!(!res.attributes.iter().any(|a| a.key == "category"));
+        assert!(by_category(deps.as_ref(), "").is_empty());
+        assert_eq!(
+            vec![Addr::unchecked("bob"), Addr::unchecked("carol")],
+            by_category(deps.as_ref(), "grants")
+        );
 
-        // ensure BALANCES doesn't contain recipeint_a anymore
-        assert!(!BALANCES.has(&deps.storage, Addr::unchecked("recipient_a")));
+        let info = mock_info("sender", &coins(10, "usei"));
+        let long = "x".repeat(65);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            transfer("bob", "carol", Some(&long)),
+        );
+        match res.unwrap_err() {
+            ContractError::CustomError { val } => {
+                assert_eq!("category must be at most 64 bytes", val)
+            }
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn repeat_fee_escalation() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            send_fee: Uint128::from(2u32),
+            repeat_window_secs: 60,
+            repeat_fee_multiplier: 3,
+            ..Default::default()
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let fee_sent = |res: &Response| match &res.messages[0].msg {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => amount[0].amount.u128(),
+            _ => panic!(),
+        };
+
+        let info = mock_info("sender", &coins(12, "usei"));
+        let res = execute_transfer(deps.as_mut(), mock_env(), info, "alice", "bob").unwrap();
+        assert_eq!(2, fee_sent(&res));
+        assert!(!res.attributes.iter().any(|a| a.key == "escalated_fee"));
+
+        // a rapid second transfer pays three times the fee
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(10);
+        let info = mock_info("sender", &coins(12, "usei"));
+        let res = execute_transfer(deps.as_mut(), env.clone(), info, "alice", "bob").unwrap();
+        assert_eq!(6, fee_sent(&res));
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "escalated_fee" && a.value == "6"));
+        assert_eq!(
+            Uint128::from(8u32),
+            BALANCES
+                .load(&deps.storage, Addr::unchecked("alice"))
+                .unwrap()
+        );
+        // other senders are unaffected
+        let info = mock_info("other", &coins(12, "usei"));
+        let res = execute_transfer(deps.as_mut(), env.clone(), info, "alice", "bob").unwrap();
+        assert_eq!(2, fee_sent(&res));
+
+        // the normal fee is back once the window has passed
+        env.block.time = env.block.time.plus_seconds(60);
+        let info = mock_info("sender", &coins(12, "usei"));
+        let res = execute_transfer(deps.as_mut(), env, info, "alice", "bob").unwrap();
+        assert_eq!(2, fee_sent(&res));
     }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,11 +6,47 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
+
+    #[error("TooSmallToSplit: {amount} usei left after the fee is too small to split between the recipients")]
+    TooSmallToSplit { amount: Uint128 },
+
+    #[error("ShareTooSmall: each recipient would get {share} but the minimum is {min_share}")]
+    ShareTooSmall { share: Uint128, min_share: Uint128 },
+
+    #[error("Insolvent: requested {requested} but the contract only holds {available}")]
+    Insolvent {
+        requested: Uint128,
+        available: Uint128,
+    },
+
+    #[error("FeeTooHighForAmount: a fee of {fee} is more than {max_fee_pct_bps} bps of {funds}")]
+    FeeTooHighForAmount {
+        fee: Uint128,
+        funds: Uint128,
+        max_fee_pct_bps: u16,
+    },
+
+    #[error("DepositTooSmall: sent {funds} usei but the minimum deposit is {min_deposit}")]
+    DepositTooSmall {
+        funds: Uint128,
+        min_deposit: Uint128,
+    },
+
+    #[error(
+        "AccountLimitReached: the contract already holds the maximum of {max_accounts} accounts"
+    )]
+    AccountLimitReached { max_accounts: u64 },
+
+    #[error("MemoRequired: transfers must carry a memo")]
+    MemoRequired {},
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
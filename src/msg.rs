@@ -1,23 +1,348 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Timestamp, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+use crate::state::{PauseState, TransferRecord};
+
+/// version of the message wire format. bump it whenever a message enum changes in a way older
+/// clients can't parse. independent of the cw2 contract version
+pub const MSG_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default)]
 pub struct InstantiateMsg {
+    /// the contract owner. defaults to the instantiating address
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// the address that transfer fees are sent to. defaults to the owner
+    #[serde(default)]
+    pub fee_recipient: Option<String>,
+    /// reject transfers that name the fee recipient as a recipient. defaults to false
+    #[serde(default)]
+    pub strict_fee_recipient: bool,
     /// units are in usei
     pub send_fee: Uint128,
+    /// the lowest fee UpdateSendFee may set. defaults to 0
+    #[serde(default)]
+    pub min_fee_bound: Uint128,
+    /// the highest fee UpdateSendFee may set. defaults to no limit
+    #[serde(default)]
+    pub max_fee_bound: Option<Uint128>,
+    /// switches to percentage fee mode: the fee is this many basis points of the funds sent,
+    /// instead of the flat `send_fee`
+    #[serde(default)]
+    pub fee_bps: Option<u16>,
+    /// in percentage fee mode, the smallest fee charged. units are in usei
+    #[serde(default)]
+    pub min_fee: Uint128,
+    /// in percentage fee mode, the largest fee charged. must not be below `min_fee`. defaults to
+    /// no cap
+    #[serde(default)]
+    pub max_fee: Option<Uint128>,
+    /// every this many transfers from the same sender, the fee is returned to the sender instead
+    /// of the fee recipient. defaults to 0 (no rebates)
+    #[serde(default)]
+    pub rebate_every: u64,
+    /// in percentage fee mode, round the fee up instead of down. defaults to false
+    #[serde(default)]
+    pub round_fee_up: bool,
+    /// leave the usei fee in the split so the recipients share the whole deposit. a fee paid in
+    /// `fee_denom` is still collected. defaults to false
+    #[serde(default)]
+    pub fee_to_recipients: bool,
+    /// reject transfers whose fee is more than this many basis points of the deposit
+    #[serde(default)]
+    pub max_fee_pct_bps: Option<u16>,
+    /// basis points of a `TransferWithReferral` deposit, after the fee, credited to the referrer.
+    /// defaults to 0
+    #[serde(default)]
+    pub referral_bps: u16,
+    /// the smallest amount each recipient may be credited by a transfer. defaults to 0 (no minimum)
+    #[serde(default)]
+    pub min_share: Uint128,
+    /// collect the flat `send_fee` in this denom instead of usei. transfers must then include
+    /// both the usei to split and the fee coin
+    #[serde(default)]
+    pub fee_denom: Option<String>,
+    /// reject transfers to contract addresses, which may not be able to withdraw. defaults to false
+    #[serde(default)]
+    pub reject_contract_recipients: bool,
+    /// reject transfers to accounts that haven't called `Register`. defaults to false
+    #[serde(default)]
+    pub require_registered_recipients: bool,
+    /// whether a sender may list itself as a recipient. defaults to true
+    #[serde(default)]
+    pub allow_sender_as_recipient: Option<bool>,
+    /// number of decimals front-ends should use to display usei amounts. defaults to 6
+    #[serde(default)]
+    pub denom_decimals: Option<u8>,
+    /// number of seconds an `UpdateSendFee` waits before it can be applied. defaults to 0 (immediate)
+    #[serde(default)]
+    pub config_timelock_secs: u64,
+    /// largest amount a single withdrawal may pay out. defaults to no limit
+    #[serde(default)]
+    pub max_withdraw_per_tx: Option<Uint128>,
+    /// minimum number of seconds between two withdrawals from the same account. defaults to 0 (no cooldown)
+    #[serde(default)]
+    pub withdraw_cooldown_secs: u64,
+    /// a transfer sent within this many seconds of the same sender's previous transfer pays the
+    /// usei fee times `repeat_fee_multiplier`. defaults to 0 (no escalation)
+    #[serde(default)]
+    pub repeat_window_secs: u64,
+    /// factor applied to the fee of a repeat transfer. must be at least 1 when a window is set
+    #[serde(default)]
+    pub repeat_fee_multiplier: u32,
+    /// seconds without a credit or withdrawal after which the owner may reclaim a balance with
+    /// `ReclaimInactive`. defaults to never
+    #[serde(default)]
+    pub inactivity_period_secs: Option<u64>,
+    /// check the contract holds enough usei before paying out a withdrawal. defaults to false
+    #[serde(default)]
+    pub verify_solvency_on_withdraw: bool,
+    /// when the solvency check fails, pause transfers and skip the payout instead of returning an
+    /// error. defaults to false
+    #[serde(default)]
+    pub auto_pause_on_insolvency: bool,
+    /// refund coins attached to a withdrawal instead of rejecting it. defaults to false
+    #[serde(default)]
+    pub refund_accidental_funds: bool,
+    /// refund whatever a transfer deposit holds beyond the largest even split instead of rejecting
+    /// it. defaults to false
+    #[serde(default)]
+    pub overpayment_refund: bool,
+    /// accept transfer denoms in any case, e.g. `USEI`. ibc denoms are still matched exactly.
+    /// defaults to false
+    #[serde(default)]
+    pub normalize_denom: bool,
+    /// only credit and pay out to addresses starting with this prefix, e.g. "sei1"
+    #[serde(default)]
+    pub required_prefix: Option<String>,
+    /// pay recipients their share immediately instead of crediting a balance. defaults to false
+    #[serde(default)]
+    pub auto_withdraw: bool,
+    /// reject `Transfer` and the other transfer variants in favour of `TransferWithMemo`.
+    /// defaults to false
+    #[serde(default)]
+    pub require_memo: bool,
+    /// the most accounts that may hold a balance at once. defaults to no limit
+    #[serde(default)]
+    pub max_accounts: Option<u64>,
+    /// the most recipients a single transfer may credit, e.g. through `TransferToGroup`. defaults
+    /// to no limit
+    #[serde(default)]
+    pub max_recipients: Option<u32>,
+    /// a withdrawal that would leave less than this many usei behind pays the remainder to the
+    /// owner and closes the account. defaults to 0 (never)
+    #[serde(default)]
+    pub dust_threshold: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    /// the funds are split evenly across the two accounts.
+    /// the funds are split evenly across the two accounts. a `category` of at most 64 bytes tags
+    /// both credited balances for `BalancesByCategory`, replacing any earlier tag. an empty
+    /// category is treated as none.
     Transfer {
         recipient_a: String,
         recipient_b: String,
+        #[serde(default)]
+        category: Option<String>,
+    },
+    /// like `Transfer`, with a memo of at most 256 bytes echoed as an attribute.
+    TransferWithMemo {
+        recipient_a: String,
+        recipient_b: String,
+        memo: String,
+    },
+    /// like `Transfer`, but `referral_bps` of the funds left after the fee are credited to
+    /// `referrer` before the rest is split.
+    TransferWithReferral {
+        recipient_a: String,
+        recipient_b: String,
+        referrer: String,
+    },
+    /// like `Transfer`, but recipient_b's share is locked until `ReleaseEscrow` reveals a preimage
+    /// whose sha256 is `release_code_hash`.
+    TransferWithEscrow {
+        recipient_a: String,
+        recipient_b: String,
+        release_code_hash: Binary,
+    },
+    /// credit an escrowed share to its recipient. anyone holding the preimage can call this.
+    ReleaseEscrow { escrow_id: u64, preimage: Binary },
+    /// store the caller's default recipients for `TransferToMyDefault`.
+    SetDefaultPair {
+        recipient_a: String,
+        recipient_b: String,
+    },
+    /// the funds are split evenly across the caller's default recipients.
+    TransferToMyDefault {},
+    /// the funds are split evenly across the members of a group set by the owner.
+    TransferToGroup { name: String },
+    /// the funds, after the fee, are split across `recipients` in proportion to their weights.
+    /// units lost to rounding go to the highest weights first.
+    TransferWeighted { recipients: Vec<WeightedRecipient> },
+    /// the funds, after the fee, are split across a page of up to `limit` balance holders after
+    /// `start_after`, in proportion to their balances. the page is capped at 50 and at
+    /// `max_recipients`, and the `last_holder` attribute is where the next page starts. holders
+    /// that can't receive transfers are skipped, and shares that round below `min_share` are
+    /// refunded to the sender.
+    DistributePro {
+        #[serde(default)]
+        start_after: Option<String>,
+        #[serde(default)]
+        limit: Option<u32>,
+    },
+    /// withdraw some or all of an accounts balance. the optional note is echoed back as an
+    /// attribute so treasury systems can tag withdrawals.
+    Withdraw {
+        amount: Uint128,
+        #[serde(default)]
+        note: Option<String>,
+    },
+    /// debit `amount` from the caller's balance and split it to two new recipients, as if it had
+    /// been sent with `Transfer`. the fee is taken out of `amount`.
+    WithdrawAndTransfer {
+        amount: Uint128,
+        recipient_a: String,
+        recipient_b: String,
+    },
+    /// split the caller's entire balance to two new recipients, paying the fee out of it.
+    ForwardSplit {
+        recipient_a: String,
+        recipient_b: String,
+    },
+    /// withdraw a percentage of an accounts balance, in basis points. 10000 withdraws everything.
+    WithdrawPercent { bps: u16 },
+    /// withdraw an accounts entire balance.
+    WithdrawAll {},
+    /// pay the caller's future withdrawals out to `addr`. can be set before the caller is credited.
+    SetWithdrawalAddress { addr: String },
+    /// credit `addr` instead of the caller in future transfers.
+    SetForwardingAddress { addr: String },
+    /// stop forwarding the caller's credits.
+    ClearForwarding {},
+    /// register the caller as an account that may receive transfers.
+    Register {},
+    /// return the caller's entire balance to the sender that credited it.
+    Reject {},
+    /// owner only. move an account's entire balance to the owner, recording why.
+    SeizeAccount { account: String, reason: String },
+    /// owner only. hand the contract over to `new_owner` and route fees to `new_fee_recipient` in
+    /// the same step.
+    Handover {
+        new_owner: String,
+        new_fee_recipient: String,
+    },
+    /// owner only. let `addr` update fees and pauses.
+    AddAdmin { addr: String },
+    /// owner only. revoke an admin added with `AddAdmin`.
+    RemoveAdmin { addr: String },
+    /// owner only. block withdrawals from each of `accounts` until unfrozen. at most 50 at once.
+    FreezeMany { accounts: Vec<String> },
+    /// owner only. lift a freeze from each of `accounts`. at most 50 at once.
+    UnfreezeMany { accounts: Vec<String> },
+    /// owner only. cap how much `account` can withdraw per day, counted in utc days of block
+    /// time. `None` removes the cap.
+    SetWithdrawLimit {
+        account: String,
+        limit: Option<Uint128>,
+    },
+    /// owner only. send the whole balance of `account` to the owner once it has been inactive for
+    /// longer than the configured inactivity period.
+    ReclaimInactive { account: String },
+    /// owner only. block withdrawals from `account` until `until`. the hold expires by itself.
+    HoldAccount { account: String, until: Timestamp },
+    /// owner only. move the whole balance of `from` to `to`, for example when `from` is compromised.
+    Reassign { from: String, to: String },
+    /// owner or admin only. pause transfers and withdrawals independently. `None` leaves a flag
+    /// unchanged.
+    SetPause {
+        transfers: Option<bool>,
+        withdrawals: Option<bool>,
+    },
+    /// owner or admin only. while enabled, transfers are free and the whole deposit is split.
+    SetFeeHoliday { enabled: bool },
+    /// owner or admin only. change the flat send_fee, within the bounds fixed at instantiation.
+    UpdateSendFee { fee: Uint128 },
+    /// apply a queued `UpdateSendFee` once its timelock has passed. anyone can call this.
+    ApplyPending {},
+    /// owner only. deposit usei into the fee pool without touching any user balance.
+    FundFeePool {},
+    /// owner only. withdraw from the fee pool. withdraws everything when `amount` is not set.
+    ClaimFees { amount: Option<Uint128> },
+    /// owner or admin only. charge `sender` a fixed `fee` for every transfer instead of the
+    /// configured fee. not available while `config_timelock_secs` is set.
+    SetSenderFee { sender: String, fee: Uint128 },
+    /// owner or admin only. charge `fee` for transfers of `denom` instead of the flat `send_fee`.
+    /// only usei is accepted, since that is the only denom transfers carry. not available while
+    /// `config_timelock_secs` is set, since the fee would change without the timelock.
+    SetDenomFee { denom: String, fee: Uint128 },
+    /// owner or admin only. remove the fee override for `sender`.
+    ClearSenderFee { sender: String },
+    /// owner only. permanently stop transfers so the contract can be wound down with `RefundAll`.
+    Decommission {},
+    /// owner only. pay out up to `limit` balances to their holders, at their withdrawal addresses
+    /// if they registered one, then return open escrows to their senders. frozen and held
+    /// balances are skipped and left for the owner. only available once decommissioned. call
+    /// repeatedly until the `refunded` attribute is 0.
+    RefundAll { limit: u32 },
+    /// owner only. pay out the whole balance of each of `accounts`, to its withdrawal address if it
+    /// registered one. accounts without a balance, frozen, on hold, or whose balance the withdraw
+    /// cooldown, per-transaction cap, daily limit or contract balance wouldn't allow are skipped.
+    /// at most 50 at once.
+    ClaimFor { accounts: Vec<String> },
+    /// owner only. change the most accounts that may hold a balance at once. `None` removes the
+    /// cap.
+    SetMaxAccounts { max_accounts: Option<u64> },
+    /// owner only. recompute `total_outstanding` and the account count from the stored balances.
+    /// iterates every balance, so on a large contract this can run out of gas.
+    ReconcileCounters {},
+    /// owner only. require every `Transfer` to carry a memo, i.e. be sent as `TransferWithMemo`.
+    /// the other transfer variants can't carry one and are rejected while this is set.
+    SetRequireMemo { required: bool },
+    /// owner only. prefix every attribute key of execute responses with `<prefix>_`, e.g.
+    /// `myapp_action`. `None` restores the plain keys.
+    SetAttrPrefix { prefix: Option<String> },
+    /// owner only. send the contract's whole balance of `denom`, which must not be usei, to
+    /// `recipient`. for coins sent to the contract by mistake.
+    RescueToken { denom: String, recipient: String },
+    /// owner only. record the fees collected so far at the current block height.
+    SnapshotFees {},
+    /// owner only. create or replace a named group of recipients for `TransferToGroup`.
+    SetGroup { name: String, members: Vec<String> },
+    /// owner only. credit balances carried over from a predecessor contract. the attached coins
+    /// must equal the sum of the imported amounts. balances in a `denom` other than usei (the
+    /// default) are held as legacy balances until their holders `Consolidate` them.
+    ImportBalances {
+        entries: Vec<BalanceEntry>,
+        #[serde(default)]
+        denom: Option<String>,
+    },
+    /// owner only. let holders of `from_denom` legacy balances `Consolidate` them into
+    /// `to_denom`, crediting `rate` units per legacy unit. `to_denom` must be usei.
+    SetDenomMigration {
+        from_denom: String,
+        to_denom: String,
+        rate: Uint128,
     },
-    /// withdraw some or all of an accounts balance.
-    Withdraw { amount: Uint128 },
+    /// move the caller's legacy balances in every migrated denom into their usei balance. the
+    /// contract must already hold the usei to back the credit; the legacy coins stay behind for
+    /// the owner to `RescueToken`.
+    Consolidate {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WeightedRecipient {
+    pub addr: String,
+    /// must be nonzero
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BalanceEntry {
+    pub account: String,
+    pub amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -25,10 +350,124 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     /// view the contract owner
     GetOwner {},
-    /// view the fee incurred by the Transfer transaction
+    /// view the flat fee incurred by the Transfer transaction, including a usei `SetDenomFee`
     GetSendFee {},
+    /// whether `addr` is the owner or an admin.
+    IsAdmin { addr: String },
+    /// view the fee configuration and display metadata
+    GetConfig {},
+    /// view a single config value, stringified. `key` is one of "owner", "send_fee", "denom" or
+    /// "paused". "paused" is true while either transfers or withdrawals are paused.
+    ConfigValue { key: String },
     /// view the balance for an account.
     GetBalance { account: String },
+    /// list the denoms in which the contract currently owes balances to accounts.
+    ActiveDenoms {},
+    /// view what each recipient would be credited for a deposit of `amount` usei.
+    ShareFor { amount: Uint128 },
+    /// view the most recipients a transfer may have. without a configured cap this is a
+    /// conservative default of 50.
+    MaxRecipients {},
+    /// view the version of the message wire format, for clients gating on compatibility.
+    SchemaVersion {},
+    /// whether a deposit of `amount` usei leaves an even amount after the fee.
+    IsEven { amount: Uint128 },
+    /// view the smallest deposit a transfer accepts: the fee plus the smallest valid share for
+    /// each of the two recipients.
+    MinDeposit {},
+    /// view the accounts whose current balance was created between `from_height` and `to_height`,
+    /// inclusive. at most 30 accounts are returned, oldest first. reads are bounded by the height
+    /// index, so gas grows with the number of accounts returned rather than the number held.
+    AccountsCreatedBetween { from_height: u64, to_height: u64 },
+    /// view the accounts whose balance is tagged with `category`, in address order.
+    BalancesByCategory {
+        category: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// view up to `limit` accounts with the smallest balances, smallest first. `limit` is capped
+    /// at 50. reads every balance, so gas grows with the number of accounts held.
+    SmallestHolders { limit: u32 },
+    /// view when the hold on `account` ends, if it is currently held.
+    HoldUntil { account: String },
+    /// whether `addr` has registered to receive transfers.
+    IsRegistered { addr: String },
+    /// view the earliest time `account` can withdraw again, taking its hold, the withdraw cooldown
+    /// and a used up daily limit into account. freezes have no end time and aren't reflected.
+    WithdrawableAt { account: String },
+    /// view where credits to `account` are forwarded, if anywhere.
+    ForwardingAddress { account: String },
+    /// view where withdrawals from `account` are paid out, if it registered an address.
+    WithdrawalAddress { account: String },
+    /// validate `input` as an address and return its canonical form.
+    NormalizeAddress { input: String },
+    /// whether `account` has ever received a transfer.
+    IsKnownAccount { account: String },
+    /// view the most recent transfer, if any.
+    LatestTransfer {},
+    /// list the transfers made by `sender`, oldest first. `start_after` is a transfer id.
+    TransfersBySender {
+        sender: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// list the transfers that credited `recipient`, oldest first. `start_after` is a transfer id.
+    TransfersToRecipient {
+        recipient: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// view fee snapshots in ascending height order.
+    FeeSnapshots {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// view the usei held by the contract on behalf of the owner.
+    FeePool {},
+    /// compare the stored balance counters with a live count of the balances. reads every balance,
+    /// so gas grows with the number of accounts held. a drift means `ReconcileCounters` is needed.
+    CounterDrift {},
+    /// view what the owner can claim from the fee pool now and the fees collected over the
+    /// contract's lifetime.
+    OwnerEarnings {},
+    /// view the total fees `sender` has paid.
+    SenderFeesPaid { sender: String },
+    /// view how many transfers `sender` has made.
+    SenderTxCount { sender: String },
+    /// view the fee override for `sender`, if any.
+    SenderFee { sender: String },
+    /// view the default recipients of `sender`, if set.
+    DefaultPair { sender: String },
+    /// view the members of a group.
+    Group { name: String },
+    /// compare what the contract owes against what it holds.
+    Accounting {},
+    /// view the config, pause flags and accounting in a single query.
+    Overview {},
+    /// view whether the contract is active, paused or decommissioned.
+    Status {},
+    /// view the contract's running counters at once.
+    Stats {},
+    /// view every boolean setting at once.
+    Flags {},
+    /// view when the contract was instantiated and, if it has been, decommissioned.
+    Lifecycle {},
+    /// view the entire contract State. only available with the `debug-queries` feature
+    #[cfg(feature = "debug-queries")]
+    DumpState {},
+    /// view what both recipients' balances would become if `amount` usei were sent to them.
+    /// a recipient with a forwarding address is shown as that address.
+    PreviewTransfer {
+        recipient_a: String,
+        recipient_b: String,
+        amount: Uint128,
+    },
+    /// view the credits and fee that sending `amount` usei with the transfer message `msg` would
+    /// produce. the sender's own fee override isn't known here and isn't applied.
+    SimulateTransferMsg {
+        msg: Box<ExecuteMsg>,
+        amount: Uint128,
+    },
 }
 
 // We define a custom struct for each query response
@@ -42,8 +481,290 @@ pub struct GetSendFeeResponse {
     pub fee: Uint128,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ConfigValueResponse {
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GetConfigResponse {
+    pub owner: Addr,
+    pub fee_recipient: Addr,
+    pub send_fee: Uint128,
+    pub fee_bps: Option<u16>,
+    pub min_fee: Uint128,
+    pub max_fee: Option<Uint128>,
+    pub max_fee_pct_bps: Option<u16>,
+    pub referral_bps: u16,
+    pub min_fee_bound: Uint128,
+    pub max_fee_bound: Uint128,
+    pub fee_denom: Option<String>,
+    pub denom_decimals: u8,
+    pub max_accounts: Option<u64>,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct GetBalanceResponse {
     pub balance: Uint128,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ActiveDenomsResponse {
+    pub denoms: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BalancePreview {
+    pub current: Uint128,
+    pub projected: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulatedCredit {
+    /// the account actually credited, after forwarding
+    pub addr: Addr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateTransferMsgResponse {
+    /// in the order the message lists the recipients, followed by any referrer
+    pub credits: Vec<SimulatedCredit>,
+    pub fee: Uint128,
+    /// units that would be refunded to the sender
+    pub refund: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PreviewTransferResponse {
+    pub recipient_a: BalancePreview,
+    pub recipient_b: BalancePreview,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Active,
+    /// either transfers or withdrawals are paused
+    Paused,
+    /// takes precedence over `Paused`
+    Decommissioned,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StatsResponse {
+    pub transfer_count: u64,
+    pub total_fees_collected: Uint128,
+    pub total_outstanding: Uint128,
+    pub account_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FlagsResponse {
+    pub transfers_paused: bool,
+    pub withdrawals_paused: bool,
+    pub decommissioned: bool,
+    pub fee_holiday: bool,
+    pub fee_to_recipients: bool,
+    pub strict_fee_recipient: bool,
+    pub round_fee_up: bool,
+    pub reject_contract_recipients: bool,
+    pub require_registered_recipients: bool,
+    pub allow_sender_as_recipient: bool,
+    pub verify_solvency_on_withdraw: bool,
+    pub auto_pause_on_insolvency: bool,
+    pub refund_accidental_funds: bool,
+    pub overpayment_refund: bool,
+    pub normalize_denom: bool,
+    pub auto_withdraw: bool,
+    pub require_memo: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LifecycleResponse {
+    pub instantiated_at: Timestamp,
+    pub decommissioned_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StatusResponse {
+    pub status: Status,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OverviewResponse {
+    pub config: GetConfigResponse,
+    pub pause: PauseState,
+    pub fee_pool: Uint128,
+    pub total_outstanding: Uint128,
+    pub total_escrowed: Uint128,
+    pub contract_balance: Uint128,
+    /// true when the contract holds at least `total_outstanding + fee_pool + total_escrowed`
+    pub solvent: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AccountingResponse {
+    pub total_user_balances: Uint128,
+    pub fee_pool: Uint128,
+    pub total_escrowed: Uint128,
+    pub contract_balance: Uint128,
+    /// true when the contract holds at least `total_user_balances + fee_pool + total_escrowed`
+    pub reconciled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LatestTransferResponse {
+    pub transfer: Option<TransferRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TransfersResponse {
+    pub transfers: Vec<TransferRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SenderFeesPaidResponse {
+    pub fees_paid: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SenderTxCountResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DefaultPairResponse {
+    pub pair: Option<(Addr, Addr)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GroupResponse {
+    pub members: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SenderFeeResponse {
+    /// `None` when the sender pays the configured fee
+    pub fee: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeSnapshot {
+    pub height: u64,
+    pub total_fees_collected: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeSnapshotsResponse {
+    pub snapshots: Vec<FeeSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeePoolResponse {
+    pub fee_pool: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CounterDriftResponse {
+    pub stored_total: Uint128,
+    pub computed_total: Uint128,
+    pub stored_count: u64,
+    pub computed_count: u64,
+    /// whether either stored counter differs from the computed one
+    pub drift: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OwnerEarningsResponse {
+    /// the current fee pool
+    pub claimable: Uint128,
+    /// every transfer fee charged so far, in the fee denom
+    pub lifetime: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ShareForResponse {
+    pub share: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct IsRegisteredResponse {
+    pub registered: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct IsAdminResponse {
+    pub admin: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MaxRecipientsResponse {
+    pub max_recipients: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SchemaVersionResponse {
+    pub msg_version: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct IsEvenResponse {
+    pub even: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MinDepositResponse {
+    pub min_deposit: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AccountsResponse {
+    pub accounts: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Holder {
+    pub account: Addr,
+    pub balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct HoldersResponse {
+    pub holders: Vec<Holder>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WithdrawableAtResponse {
+    /// `None` when the account can withdraw now
+    pub at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct HoldUntilResponse {
+    /// `None` when the account isn't held or its hold has expired
+    pub until: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ForwardingAddressResponse {
+    /// `None` when credits to the account aren't forwarded
+    pub addr: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WithdrawalAddressResponse {
+    /// `None` when withdrawals are paid to the account itself
+    pub addr: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NormalizeAddressResponse {
+    pub addr: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct IsKnownAccountResponse {
+    pub known: bool,
+}
@@ -1,17 +1,218 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct State {
     pub owner: Addr,
-    /// every send incurs a small fee, which is sent to the owner of the contract
+    /// receives the fee of every transfer
+    pub fee_recipient: Addr,
+    /// transfers may not credit the fee recipient, to keep fee income and balances separate
+    pub strict_fee_recipient: bool,
+    /// every send incurs a small fee, which is sent to the fee recipient
     /// this contract only supports the usei coin
     pub send_fee: Uint128,
+    /// `send_fee` can never be updated below this
+    pub min_fee_bound: Uint128,
+    /// `send_fee` can never be updated above this
+    pub max_fee_bound: Uint128,
+    /// percentage fee mode: when set, the fee is this many basis points of the funds instead of `send_fee`
+    pub fee_bps: Option<u16>,
+    /// floor for the percentage fee
+    pub min_fee: Uint128,
+    /// cap for the percentage fee
+    pub max_fee: Option<Uint128>,
+    /// every this many transfers from a sender, the fee goes back to the sender. 0 disables rebates
+    pub rebate_every: u64,
+    /// round the percentage fee up so the owner never loses a fractional unit
+    pub round_fee_up: bool,
+    /// transfers whose fee is more than this many basis points of the deposit are rejected
+    pub max_fee_pct_bps: Option<u16>,
+    /// share of a referral transfer, after the fee, that goes to the referrer, in basis points
+    pub referral_bps: u16,
+    /// transfers that would credit each recipient less than this are rejected
+    pub min_share: Uint128,
+    /// when set, the fee is paid in this denom on top of the usei being split
+    pub fee_denom: Option<String>,
+    /// transfers to contract addresses are rejected, since their balance could be stranded
+    pub reject_contract_recipients: bool,
+    /// transfers are only credited to accounts in `REGISTERED`
+    pub require_registered_recipients: bool,
+    /// when false, transfers naming the sender as a recipient are rejected
+    pub allow_sender_as_recipient: bool,
+    /// display metadata for front-ends. doesn't affect any math
+    pub denom_decimals: u8,
+    /// which operations are currently paused by the owner
+    pub pause: PauseState,
+    /// set once the owner starts winding the contract down. no new balances can be created
+    pub decommissioned: bool,
+    /// block time the contract was instantiated at
+    pub instantiated_at: Timestamp,
+    /// block time of the decommission, once it has happened
+    pub decommissioned_at: Option<Timestamp>,
+    /// while true no fee is charged, whatever the other fee settings are
+    pub fee_holiday: bool,
+    /// the usei fee is computed but split between the recipients instead of collected
+    pub fee_to_recipients: bool,
+    /// delay before a queued `send_fee` update can be applied. 0 applies updates immediately
+    pub config_timelock_secs: u64,
+    /// a `send_fee` update waiting out the timelock
+    pub pending_fee: Option<PendingFee>,
+    /// withdrawals above this are rejected, so large balances take several transactions
+    pub max_withdraw_per_tx: Option<Uint128>,
+    /// minimum number of seconds an account has to wait between withdrawals
+    pub withdraw_cooldown_secs: u64,
+    /// transfers this soon after the sender's previous one pay an escalated fee. 0 disables it
+    pub repeat_window_secs: u64,
+    /// factor the usei fee is multiplied by for a repeat transfer
+    pub repeat_fee_multiplier: u32,
+    /// balances untouched for longer than this can be reclaimed by the owner. `None` disables it
+    pub inactivity_period_secs: Option<u64>,
+    /// query the contract's own balance before paying out a withdrawal
+    pub verify_solvency_on_withdraw: bool,
+    /// pause transfers instead of failing when the solvency check finds a shortfall
+    pub auto_pause_on_insolvency: bool,
+    /// refund coins attached to a withdrawal instead of rejecting it
+    pub refund_accidental_funds: bool,
+    /// refund the part of a transfer that doesn't split evenly instead of rejecting the transfer
+    pub overpayment_refund: bool,
+    /// compare transfer denoms case-insensitively, except for ibc denoms
+    pub normalize_denom: bool,
+    /// recipients and withdrawing accounts must start with this prefix
+    pub required_prefix: Option<String>,
+    /// transfers pay recipients directly instead of crediting `BALANCES`
+    pub auto_withdraw: bool,
+    /// transfers without a memo are rejected
+    pub require_memo: bool,
+    /// credits that would create an account beyond this many are rejected. top-ups are unaffected
+    pub max_accounts: Option<u64>,
+    /// transfers to more recipients than this are rejected
+    pub max_recipients: Option<u32>,
+    /// a withdrawal that would leave less than this behind sweeps the remainder to the owner
+    pub dust_threshold: Uint128,
+    /// prepended, with an underscore, to every attribute key of execute responses
+    pub attr_prefix: Option<String>,
+    /// number of entries in `BALANCES`
+    pub account_count: u64,
+    /// sum of every entry in `BALANCES`. decrements saturate at zero; any drift from the real sum
+    /// is a bug
+    pub total_outstanding: Uint128,
+    /// usei held by the contract on behalf of the owner
+    pub fee_pool: Uint128,
+    /// every transfer fee charged so far, in the fee denom
+    pub total_fees_collected: Uint128,
+    /// number of transfers made. also the id of the next `HISTORY` entry
+    pub transfer_count: u64,
+    /// sum of every entry in `ESCROWS`. held by the contract but not part of any balance
+    pub total_escrowed: Uint128,
+    /// id of the next `ESCROWS` entry
+    pub escrow_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct PauseState {
+    /// rejects new transfers
+    pub transfers: bool,
+    /// rejects withdrawals of every kind
+    pub withdrawals: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingFee {
+    pub fee: Uint128,
+    /// the earliest block time the update can be applied at
+    pub effective_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TransferRecord {
+    pub id: u64,
+    pub sender: Addr,
+    pub recipient_a: Addr,
+    pub recipient_b: Addr,
+    /// the amount credited to each recipient
+    pub share: Uint128,
+    pub fee: Uint128,
+    pub block_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Escrow {
+    pub sender: Addr,
+    pub recipient: Addr,
+    pub amount: Uint128,
+    /// sha256 of the preimage that releases the escrow
+    pub release_code_hash: Binary,
 }
 
 pub const STATE: Item<State> = Item::new("state");
+/// addresses allowed to change fees and pauses alongside the owner
+pub const ADMINS: Map<Addr, ()> = Map::new("admins");
 /// stores the withdrawable balance of every account that this contract was used to send coins to
 pub const BALANCES: Map<Addr, Uint128> = Map::new("balances");
+/// block height each entry in `BALANCES` was created at. removed along with the balance
+pub const CREATED_HEIGHT: Map<Addr, u64> = Map::new("created_height");
+/// index of `CREATED_HEIGHT` by height. keyed by (created height, account)
+pub const ACCOUNTS_BY_HEIGHT: Map<(u64, Addr), ()> = Map::new("accounts_by_height");
+/// lifetime amount received through transfers by every account, whether credited or paid out directly.
+/// unlike `BALANCES`, entries are never removed
+pub const RECEIVED_TOTAL: Map<Addr, Uint128> = Map::new("received_total");
+/// where withdrawals from each account are paid out, when it differs from the account itself
+pub const WITHDRAWAL_ADDRESSES: Map<Addr, Addr> = Map::new("withdrawal_addresses");
+/// where credits to an account are redirected. only one hop is followed
+pub const FORWARDING_ADDRESSES: Map<Addr, Addr> = Map::new("forwarding_addresses");
+/// accounts that registered themselves to receive transfers
+pub const REGISTERED: Map<Addr, ()> = Map::new("registered");
+/// accounts the owner has frozen. withdrawals are rejected until they are unfrozen
+pub const FROZEN: Map<Addr, ()> = Map::new("frozen");
+/// accounts the owner has put on hold. withdrawals are rejected until the stored block time
+pub const HOLDS: Map<Addr, Timestamp> = Map::new("holds");
+/// the most each account may withdraw per day, when the owner has set a cap
+pub const WITHDRAW_LIMITS: Map<Addr, Uint128> = Map::new("withdraw_limits");
+/// usei withdrawn by each account on each day, keyed by days since the unix epoch. only tracked
+/// for accounts with a limit
+pub const DAILY_WITHDRAWN: Map<(Addr, u64), Uint128> = Map::new("daily_withdrawn");
+/// the bookkeeping category each balance was last credited under
+pub const BALANCE_CATEGORIES: Map<Addr, String> = Map::new("balance_categories");
+/// secondary index of `BALANCE_CATEGORIES` by category
+pub const ACCOUNTS_BY_CATEGORY: Map<(String, Addr), ()> = Map::new("accounts_by_category");
+/// block time of the most recent credit or withdrawal for every balance
+pub const LAST_ACTIVITY: Map<Addr, Timestamp> = Map::new("last_activity");
+/// block time of the most recent transfer from every sender. used to escalate repeat fees
+pub const LAST_TRANSFER: Map<Addr, Timestamp> = Map::new("last_transfer");
+/// block time of the most recent withdrawal for every account. used to enforce the withdraw cooldown
+pub const LAST_WITHDRAW: Map<Addr, Timestamp> = Map::new("last_withdraw");
+/// the sender that credited each balance. `None` once an account has been credited by more than one
+/// sender, in which case the original sender can no longer be determined
+pub const CREDITED_BY: Map<Addr, Option<Addr>> = Map::new("credited_by");
+/// flat fee per denom, set by the owner. `send_fee` applies to unlisted denoms. the contract only
+/// accepts usei, so only the usei entry is used
+pub const FEES: Map<String, Uint128> = Map::new("fees");
+/// per-sender fee overrides set by the owner. replaces the configured fee for that sender's transfers
+pub const SENDER_FEES: Map<Addr, Uint128> = Map::new("sender_fees");
+/// the recipients each sender's `TransferToMyDefault` splits between
+pub const DEFAULT_PAIRS: Map<Addr, (Addr, Addr)> = Map::new("default_pairs");
+/// named groups of recipients for `TransferToGroup`, set by the owner
+pub const GROUPS: Map<String, Vec<Addr>> = Map::new("groups");
+/// total fees paid by each sender, excluding rebated fees
+pub const SENDER_FEES_PAID: Map<Addr, Uint128> = Map::new("sender_fees_paid");
+/// number of transfers made by each sender
+pub const SENDER_TX_COUNT: Map<Addr, u64> = Map::new("sender_tx_count");
+/// `total_fees_collected` as of each snapshot, keyed by block height
+pub const FEE_SNAPSHOTS: Map<u64, Uint128> = Map::new("fee_snapshots");
+/// every transfer made, keyed by a sequential id
+pub const HISTORY: Map<u64, TransferRecord> = Map::new("history");
+/// shares locked by `TransferWithEscrow` until their preimage is revealed. keyed by escrow id
+pub const ESCROWS: Map<u64, Escrow> = Map::new("escrows");
+/// index of `HISTORY` by sender. keyed by (sender, history id)
+pub const TRANSFERS_BY_SENDER: Map<(Addr, u64), ()> = Map::new("transfers_by_sender");
+/// index of `HISTORY` by recipient. keyed by (recipient, history id)
+pub const TRANSFERS_BY_RECIPIENT: Map<(Addr, u64), ()> = Map::new("transfers_by_recipient");
+/// old denom -> (new denom, rate) that `Consolidate` converts legacy balances at. set by the owner
+pub const DENOM_MIGRATIONS: Map<String, (String, Uint128)> = Map::new("denom_migrations");
+/// balances imported in a denom other than usei, keyed by (account, denom)
+pub const LEGACY_BALANCES: Map<(Addr, String), Uint128> = Map::new("legacy_balances");
+/// sum of `LEGACY_BALANCES` per denom. that much of the denom can't be rescued
+pub const LEGACY_TOTALS: Map<String, Uint128> = Map::new("legacy_totals");